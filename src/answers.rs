@@ -0,0 +1,91 @@
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+/// Known answers per day and input file, stored in the TOML format:
+///
+/// ```toml
+/// [day06]
+/// "inputs/day06.txt" = ["1142", "2803"]
+/// ```
+#[derive(Debug, Default)]
+pub struct AnswerStore {
+    path: PathBuf,
+    document: Table,
+}
+
+impl AnswerStore {
+    /// Loads the store at `path`, a missing file is an empty store.
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let document = match std::fs::read_to_string(&path) {
+            Ok(text) => match text.parse() {
+                Ok(document) => document,
+                Err(e) => panic!("invalid answer store {}: {e}", path.display()),
+            },
+            Err(_) => Table::new(),
+        };
+        Self { path, document }
+    }
+
+    /// Returns the known answers of `day` for an input file.
+    pub fn expected(&self, day: &str, input: &Path) -> Option<Vec<String>> {
+        let values = self.document.get(day)?.get(key(input))?.as_array()?;
+        Some(
+            values
+                .iter()
+                .map(|value| match value {
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                })
+                .collect(),
+        )
+    }
+
+    pub fn record(&mut self, day: &str, input: &Path, answers: &[String]) {
+        let answers = answers.iter().cloned().map(Value::String).collect();
+        let section = self
+            .document
+            .entry(day)
+            .or_insert_with(|| Value::Table(Table::new()));
+        match section {
+            Value::Table(table) => {
+                table.insert(key(input), Value::Array(answers));
+            }
+            _ => panic!(
+                "invalid answer store {}: `{day}` must be a section",
+                self.path.display()
+            ),
+        }
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let text = toml::to_string(&self.document).map_err(std::io::Error::other)?;
+        std::fs::write(&self.path, text)
+    }
+}
+
+fn key(input: &Path) -> String {
+    input.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod test {
+    use super::AnswerStore;
+    use std::path::Path;
+
+    #[test]
+    fn test_record_and_reload() {
+        let path = std::env::temp_dir().join(format!("aoc_answers_{}.toml", std::process::id()));
+        let mut store = AnswerStore::load(&path);
+        let input = Path::new("inputs/day10.txt");
+        assert_eq!(store.expected("day10", input), None);
+
+        let answers = vec!["12460".to_owned(), "#..\n.##".to_owned()];
+        store.record("day10", input, &answers);
+        store.save().unwrap();
+
+        let store = AnswerStore::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(store.expected("day10", input), Some(answers));
+    }
+}
@@ -0,0 +1,341 @@
+use crate::answers::AnswerStore;
+use crate::config::{Config, OutputFormat};
+use serde_json::json;
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// The outcome of running a solver on a single input file.
+#[derive(Debug)]
+pub struct BatchResult {
+    pub input: PathBuf,
+    pub answers: Result<Vec<String>, String>,
+    /// Mean time of a single run of the solver.
+    pub elapsed: Duration,
+    /// The answers known from the answer store, if any.
+    pub expected: Option<Vec<String>>,
+}
+
+impl BatchResult {
+    pub fn status(&self) -> String {
+        match (&self.answers, &self.expected) {
+            (Err(e), _) => format!("failed: {e}"),
+            (Ok(_), None) => "ok".to_owned(),
+            (Ok(answers), Some(expected)) => {
+                let wrong: Vec<_> = (0..answers.len().max(expected.len()))
+                    .filter(|&i| answers.get(i) != expected.get(i))
+                    .map(|i| (i + 1).to_string())
+                    .collect();
+                if wrong.is_empty() {
+                    "correct".to_owned()
+                } else {
+                    format!("wrong: part {}", wrong.join(", "))
+                }
+            }
+        }
+    }
+}
+
+/// Runs `solve` on every input matched by `pattern` and prints the answers in the configured
+/// output format. With `--record` the answers are written to the answer store.
+pub fn run(config: &Config, day: &str, pattern: &str, solve: impl Fn(&str) -> Vec<String>) {
+    let inputs = match expand_inputs(pattern) {
+        Ok(inputs) if !inputs.is_empty() => inputs,
+        Ok(_) => panic!("no input files match {pattern:?}"),
+        Err(e) => panic!("could not read inputs {pattern:?}: {e}"),
+    };
+    let mut results = run_all(&inputs, config.iterations(), solve);
+
+    if let Some(path) = config.answer_store() {
+        let mut store = AnswerStore::load(path);
+        if config.args().flag("record") {
+            for result in &results {
+                if let Ok(answers) = &result.answers {
+                    store.record(day, &result.input, answers);
+                }
+            }
+            store.save().expect("could not write the answer store");
+        }
+        for result in &mut results {
+            result.expected = store.expected(day, &result.input);
+        }
+    }
+
+    match config.output_format() {
+        OutputFormat::Table => print!("{}", format_table(&results)),
+        OutputFormat::Json => println!("{}", format_json(&results)),
+    }
+}
+
+/// Expands a directory or a glob into the list of input files it refers to.
+///
+/// A directory expands to all files in it. A glob may use `*` and `?` in its last path component,
+/// e.g. `inputs/day06*.txt`. Any other path is returned as is.
+pub fn expand_inputs(pattern: &str) -> std::io::Result<Vec<PathBuf>> {
+    let path = Path::new(pattern);
+    let (dir, file_pattern) = if path.is_dir() {
+        (path, "*")
+    } else {
+        match path.file_name().and_then(|name| name.to_str()) {
+            Some(name) if name.contains(['*', '?']) => (
+                path.parent()
+                    .filter(|p| !p.as_os_str().is_empty())
+                    .unwrap_or(Path::new(".")),
+                name,
+            ),
+            _ => return Ok(vec![path.to_path_buf()]),
+        }
+    };
+
+    let mut inputs = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if entry.file_type()?.is_file() && glob_match(file_pattern, &name.to_string_lossy()) {
+            inputs.push(entry.path());
+        }
+    }
+    inputs.sort();
+    Ok(inputs)
+}
+
+/// Runs `solve` `iterations` times on each of the inputs, a panicking solver is recorded as a
+/// failure.
+pub fn run_all(
+    inputs: &[PathBuf],
+    iterations: usize,
+    solve: impl Fn(&str) -> Vec<String>,
+) -> Vec<BatchResult> {
+    // Keep the default hook from printing every failure in the middle of the table.
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let results = inputs
+        .iter()
+        .map(|input| {
+            let start = Instant::now();
+            let mut runs = 1;
+            let answers = match std::fs::read_to_string(input) {
+                Ok(contents) => panic::catch_unwind(AssertUnwindSafe(|| {
+                    let answers = solve(&contents);
+                    for _ in 1..iterations {
+                        solve(&contents);
+                        runs += 1;
+                    }
+                    answers
+                }))
+                .map_err(|payload| panic_message(payload.as_ref())),
+                Err(e) => Err(e.to_string()),
+            };
+            BatchResult {
+                input: input.clone(),
+                answers,
+                elapsed: start.elapsed() / runs,
+                expected: None,
+            }
+        })
+        .collect();
+    panic::set_hook(hook);
+    results
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "solver panicked".to_owned()
+    }
+}
+
+/// Formats the results as a table with one row per input. Answers that span multiple lines
+/// (e.g. rendered letters) continue on the following lines of the same row.
+pub fn format_table(results: &[BatchResult]) -> String {
+    let parts = results
+        .iter()
+        .filter_map(|result| result.answers.as_ref().ok())
+        .map(Vec::len)
+        .max()
+        .unwrap_or(0);
+
+    let mut header = vec!["input".to_owned()];
+    header.extend((1..=parts).map(|part| format!("part {part}")));
+    header.push("time".to_owned());
+    header.push("status".to_owned());
+
+    let rows: Vec<Vec<String>> = results
+        .iter()
+        .map(|result| {
+            let mut row = vec![result.input.display().to_string()];
+            match &result.answers {
+                Ok(answers) => {
+                    row.extend((0..parts).map(|i| answers.get(i).cloned().unwrap_or_default()))
+                }
+                Err(_) => row.extend((0..parts).map(|_| "-".to_owned())),
+            }
+            row.push(format!("{:.2?}", result.elapsed));
+            row.push(result.status());
+            row
+        })
+        .collect();
+
+    let widths: Vec<usize> = (0..header.len())
+        .map(|column| {
+            std::iter::once(&header)
+                .chain(&rows)
+                .flat_map(|row| row[column].lines())
+                .map(|line| line.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let mut table = String::new();
+    for row in std::iter::once(&header).chain(&rows) {
+        let height = row
+            .iter()
+            .map(|cell| cell.lines().count())
+            .max()
+            .unwrap_or(1);
+        for line in 0..height.max(1) {
+            let mut cells: Vec<_> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| {
+                    let text = cell.lines().nth(line).unwrap_or("");
+                    format!("{text:width$}")
+                })
+                .collect();
+            while cells.last().is_some_and(|cell| cell.trim().is_empty()) {
+                cells.pop();
+            }
+            table.push_str(cells.join(" | ").trim_end());
+            table.push('\n');
+        }
+    }
+    table
+}
+
+/// Formats the results as a JSON array with one object per input.
+pub fn format_json(results: &[BatchResult]) -> String {
+    let objects: Vec<_> = results
+        .iter()
+        .map(|result| {
+            json!({
+                "input": result.input.display().to_string(),
+                "answers": result.answers.as_ref().ok(),
+                "seconds": result.elapsed.as_secs_f64(),
+                "status": result.status(),
+            })
+        })
+        .collect();
+    serde_json::to_string_pretty(&objects).unwrap()
+}
+
+/// Matches a file name against a pattern where `*` matches any sequence of characters and `?`
+/// matches a single character.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod test {
+    use super::{format_json, format_table, glob_match, run_all, BatchResult};
+    use serde_json::{json, Value};
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*", "day06.txt"));
+        assert!(glob_match("day0?.txt", "day06.txt"));
+        assert!(glob_match("day*.txt", "day06.txt"));
+        assert!(glob_match("*06*", "day06.txt"));
+        assert!(!glob_match("day1?.txt", "day06.txt"));
+        assert!(!glob_match("*.rs", "day06.txt"));
+    }
+
+    #[test]
+    fn test_run_all_records_failures() {
+        let results = run_all(
+            &[
+                PathBuf::from("inputs/day06.txt"),
+                PathBuf::from("inputs/missing.txt"),
+            ],
+            2,
+            |input| {
+                if input.is_empty() {
+                    panic!("empty input");
+                }
+                vec![input.len().to_string()]
+            },
+        );
+        assert!(results[0].answers.is_ok());
+        assert!(results[1].answers.is_err());
+    }
+
+    #[test]
+    fn test_format() {
+        let results = [
+            BatchResult {
+                input: PathBuf::from("a.txt"),
+                answers: Ok(vec!["1".to_owned(), "##\n..".to_owned()]),
+                elapsed: Duration::ZERO,
+                expected: None,
+            },
+            BatchResult {
+                input: PathBuf::from("b.txt"),
+                answers: Err("boom".to_owned()),
+                elapsed: Duration::ZERO,
+                expected: None,
+            },
+            BatchResult {
+                input: PathBuf::from("c.txt"),
+                answers: Ok(vec!["1".to_owned(), "2".to_owned()]),
+                elapsed: Duration::ZERO,
+                expected: Some(vec!["1".to_owned(), "3".to_owned()]),
+            },
+        ];
+        assert_eq!(
+            format_table(&results),
+            "input | part 1 | part 2 | time   | status\n\
+             a.txt | 1      | ##     | 0.00ns | ok\n\
+             \x20     |        | ..\n\
+             b.txt | -      | -      | 0.00ns | failed: boom\n\
+             c.txt | 1      | 2      | 0.00ns | wrong: part 2\n"
+        );
+        let json: Value = serde_json::from_str(&format_json(&results[1..])).unwrap();
+        assert_eq!(
+            json,
+            json!([
+                {"input": "b.txt", "answers": null, "seconds": 0.0, "status": "failed: boom"},
+                {"input": "c.txt", "answers": ["1", "2"], "seconds": 0.0, "status": "wrong: part 2"},
+            ])
+        );
+    }
+}
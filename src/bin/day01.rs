@@ -1,21 +1,979 @@
+use aoc_2022::config::{Config, OutputFormat};
+use aoc_2022::{batch, checked};
+use serde_json::{json, Map, Value};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt::Write;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+
 fn main() {
-    // Read input file
-    let input = std::fs::read_to_string("inputs/day01.txt").unwrap();
-
-    let mut elf_list = Vec::new();
-    let mut current_sum = 0;
-    let lines = input.lines();
-    for line in lines {
-        if line.is_empty() {
-            elf_list.push(current_sum);
-            current_sum = 0;
+    let day = aoc_2022::day_name(file!());
+    let config = Config::from_env();
+    if config.args().flag("checked") {
+        checked::enable(day);
+    }
+    if let Some(pattern) = config.args().value("batch") {
+        batch::run(&config, day, pattern, solve);
+        return;
+    }
+
+    // Stream the input file, inventories can be much larger than memory.
+    let path = config.input_path(day);
+    let file = File::open(&path).unwrap_or_else(|e| panic!("could not open {path:?}: {e}"));
+    let reader = BufReader::new(file);
+    let format = config
+        .args()
+        .parsed("inventory_format")
+        .unwrap_or_else(|| InventoryFormat::from_path(&path));
+
+    if config.args().flag("report") {
+        let mut elves = Vec::new();
+        read_elves(reader, format, |elf| elves.push(elf)).unwrap();
+        let report = Report::new(&elves, config.day_param(day, "buckets", 10));
+        match config.output_format() {
+            OutputFormat::Table => print!("{}", report.to_table()),
+            OutputFormat::Json => println!("{}", report.to_json()),
+        }
+        return;
+    }
+
+    if let Some(target) = config.args().parsed::<u64>("target") {
+        let mut elves = Vec::new();
+        read_elves(reader, format, |elf| elves.push(elf)).unwrap();
+        if config.args().flag("exact") {
+            let budget = config.day_param(day, "search_budget", SEARCH_BUDGET);
+            match exact_subset(&elves, target, budget) {
+                Subset::Exact(elves) => println!("Exactly {target}: {}", describe(&elves)),
+                Subset::Impossible => println!("No elves carry exactly {target} calories"),
+                Subset::Approximation(elves) => {
+                    println!("Search budget exhausted, closest: {}", describe(&elves))
+                }
+            }
         } else {
-            current_sum += line.parse::<i32>().unwrap();
+            match smallest_covering_set(&elves, target) {
+                Some(elves) => println!("At least {target}: {}", describe(&elves)),
+                None => println!("All elves together carry less than {target} calories"),
+            }
+        }
+        return;
+    }
+
+    let top = config.day_param(day, "top", 3);
+    let elves = top_elves(reader, format, top.max(3)).unwrap();
+
+    println!("Solution 01: {}", most_calories(&elves));
+    println!("Solution 02: {}", total_calories(elves.iter().take(3)));
+
+    if config.args().flag("top") {
+        for (rank, elf) in elves.iter().take(top).enumerate() {
+            let name = elf.name.as_deref().unwrap_or("");
+            println!(
+                "{:>3}. elf {:<6} {name:<12} {}",
+                rank + 1,
+                elf.index,
+                elf.calories
+            );
+            for item in &elf.items {
+                let name = item.name.as_deref().unwrap_or("-");
+                println!("       {name:<24} {}", item.calories);
+            }
+        }
+    }
+}
+
+fn solve(input: &str) -> Vec<String> {
+    let elves = top_elves(input.as_bytes(), InventoryFormat::Plain, 3).unwrap();
+
+    vec![
+        most_calories(&elves).to_string(),
+        total_calories(elves.iter().take(3)).to_string(),
+    ]
+}
+
+/// The calories of the first elf, zero for an empty inventory.
+fn most_calories(elves: &[Elf]) -> u64 {
+    elves.first().map_or(0, |elf| elf.calories)
+}
+
+/// The items carried by an elf, `index` is its position in the inventory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Elf {
+    index: usize,
+    name: Option<String>,
+    items: Vec<Item>,
+    /// The total calories of the items.
+    calories: u64,
+}
+
+/// A food item, only structured inventories name their items.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Item {
+    name: Option<String>,
+    calories: u64,
+}
+
+impl Elf {
+    fn new(index: usize, name: Option<String>) -> Self {
+        Elf {
+            index,
+            name,
+            items: Vec::new(),
+            calories: 0,
+        }
+    }
+
+    fn add(&mut self, item: Item) {
+        self.calories = checked::add(self.calories, item.calories);
+        self.items.push(item);
+    }
+}
+
+/// Elves with more calories come first, ties go to the elf earlier in the inventory.
+impl Ord for Elf {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .calories
+            .cmp(&self.calories)
+            .then(self.index.cmp(&other.index))
+    }
+}
+
+impl PartialOrd for Elf {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// How an inventory is written down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InventoryFormat {
+    /// The puzzle format: the calories of one item per line and a blank line between elves.
+    Plain,
+    /// `elf,item,calories` rows with an optional `elf,item,calories` header, an elf's rows need not be adjacent.
+    Csv,
+    /// `[{"elf": "name", "items": [{"name": "apple", "calories": 1000}, ...]}, ...]`, items may
+    /// also be bare numbers.
+    Json,
+}
+
+impl InventoryFormat {
+    fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("csv") => InventoryFormat::Csv,
+            Some("json") => InventoryFormat::Json,
+            _ => InventoryFormat::Plain,
         }
     }
-    elf_list.sort();
-    elf_list.reverse();
+}
+
+impl FromStr for InventoryFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(InventoryFormat::Plain),
+            "csv" => Ok(InventoryFormat::Csv),
+            "json" => Ok(InventoryFormat::Json),
+            _ => Err(format!("unknown inventory format {s:?}")),
+        }
+    }
+}
+
+/// Returns the `n` elves carrying the most calories in an inventory, most first.
+///
+/// For plain inventories only the current line and the best `n` elves are kept in memory.
+fn top_elves(reader: impl BufRead, format: InventoryFormat, n: usize) -> std::io::Result<Vec<Elf>> {
+    // Max-heap on the `Elf` ordering, so the top holds the weakest of the best elves.
+    let mut best = BinaryHeap::with_capacity(n + 1);
+    read_elves(reader, format, |elf| {
+        best.push(elf);
+        if best.len() > n {
+            best.pop();
+        }
+    })?;
+    Ok(best.into_sorted_vec())
+}
+
+/// Reads an inventory and calls `f` for every elf in it, in order of appearance.
+fn read_elves(
+    mut reader: impl BufRead,
+    format: InventoryFormat,
+    f: impl FnMut(Elf),
+) -> std::io::Result<()> {
+    if format == InventoryFormat::Plain {
+        return read_plain_elves(reader, f);
+    }
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    let elves = match format {
+        InventoryFormat::Csv => parse_csv_elves(&text),
+        _ => parse_json_elves(&text),
+    };
+    elves
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?
+        .into_iter()
+        .for_each(f);
+    Ok(())
+}
+
+/// Reads a plain inventory line by line. The last elf does not need a trailing blank line.
+fn read_plain_elves(mut reader: impl BufRead, mut f: impl FnMut(Elf)) -> std::io::Result<()> {
+    let mut line = String::new();
+    let mut line_number = 0;
+    let mut index = 0;
+    let mut current: Option<Elf> = None;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        line_number += 1;
+        let item = line.trim();
+        if item.is_empty() {
+            if let Some(elf) = current.take() {
+                f(elf);
+                index += 1;
+            }
+        } else {
+            let calories = item.parse::<u64>().map_err(|e| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("line {line_number}: invalid calories {item:?}: {e}"),
+                )
+            })?;
+            current
+                .get_or_insert_with(|| Elf::new(index, None))
+                .add(Item {
+                    name: None,
+                    calories,
+                });
+        }
+    }
+    if let Some(elf) = current {
+        f(elf);
+    }
+    Ok(())
+}
+
+// Parses: "elf,item,calories" rows, e.g. "Ann,apple,1000"
+fn parse_csv_elves(text: &str) -> Result<Vec<Elf>, String> {
+    let mut elves: Vec<Elf> = Vec::new();
+    let mut by_name = HashMap::new();
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(text.as_bytes());
+    for (idx, record) in reader.records().enumerate() {
+        let record = record.map_err(|e| e.to_string())?;
+        let line = record.position().map_or(idx + 1, |p| p.line() as usize);
+        if record.len() != 3 {
+            return Err(format!(
+                "line {line}: expected 3 fields, found {}",
+                record.len()
+            ));
+        }
+        let is_header = ["elf", "item", "calories"]
+            .iter()
+            .zip(&record)
+            .all(|(name, field)| field.eq_ignore_ascii_case(name));
+        if idx == 0 && is_header {
+            continue;
+        }
+        let (elf, item, calories) = (&record[0], &record[1], &record[2]);
+        let calories = calories
+            .parse::<u64>()
+            .map_err(|e| format!("line {line}: invalid calories {calories:?}: {e}"))?;
+        let index = *by_name.entry(elf.to_owned()).or_insert_with(|| {
+            elves.push(Elf::new(elves.len(), Some(elf.to_owned())));
+            elves.len() - 1
+        });
+        elves[index].add(Item {
+            name: Some(item.to_owned()),
+            calories,
+        });
+    }
+    Ok(elves)
+}
+
+fn parse_json_elves(text: &str) -> Result<Vec<Elf>, String> {
+    let value: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    let entries = value.as_array().ok_or("expected an array of elves")?;
+    entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let name = entry.get("elf").and_then(Value::as_str);
+            let items = entry
+                .get("items")
+                .and_then(Value::as_array)
+                .ok_or_else(|| format!("elf {index}: expected an array of items"))?;
+            let mut elf = Elf::new(index, name.map(str::to_owned));
+            for item in items {
+                let (name, calories) = match item.get("calories") {
+                    Some(calories) => (item.get("name").and_then(Value::as_str), calories),
+                    None => (None, item),
+                };
+                // Integers are read exactly, numbers with a fraction or beyond `u64` are rejected.
+                let calories = calories
+                    .as_u64()
+                    .ok_or_else(|| format!("elf {index}: invalid calories {calories}"))?;
+                elf.add(Item {
+                    name: name.map(str::to_owned),
+                    calories,
+                });
+            }
+            Ok(elf)
+        })
+        .collect()
+}
+
+fn total_calories<'a>(elves: impl IntoIterator<Item = &'a Elf>) -> u64 {
+    checked::sum(0, elves.into_iter().map(|elf| elf.calories))
+}
+
+/// Summary statistics of an inventory.
+#[derive(Debug, PartialEq)]
+struct Report {
+    count: usize,
+    calories: Summary,
+    items: Summary,
+    /// The calories below which the given percentage of the elves fall, nearest-rank method.
+    percentiles: Vec<(usize, u64)>,
+    /// Every elf by calories, most first.
+    ranks: Vec<Rank>,
+    histogram: Vec<Bucket>,
+}
+
+/// The percentile rank of an elf's calories, the percentage of the elves carrying fewer calories
+/// with half of those carrying as many.
+#[derive(Debug, PartialEq)]
+struct Rank {
+    index: usize,
+    name: Option<String>,
+    calories: u64,
+    percentile: f64,
+}
+
+#[derive(Debug, PartialEq)]
+struct Summary {
+    min: u64,
+    max: u64,
+    mean: f64,
+    median: f64,
+}
+
+/// The number of elves carrying `from..=to` calories.
+#[derive(Debug, PartialEq)]
+struct Bucket {
+    from: u64,
+    to: u64,
+    count: usize,
+}
+
+const PERCENTILES: [usize; 7] = [1, 10, 25, 50, 75, 90, 99];
+
+/// The number of ranked elves shown in the table, the JSON report holds all of them.
+const TABLE_RANKS: usize = 10;
+
+impl Summary {
+    /// Summarizes sorted values, all zero if there are none.
+    fn new(sorted: &[u64]) -> Self {
+        let n = sorted.len();
+        if n == 0 {
+            return Summary {
+                min: 0,
+                max: 0,
+                mean: 0.0,
+                median: 0.0,
+            };
+        }
+        let median = if n.is_multiple_of(2) {
+            (sorted[n / 2 - 1] as f64 + sorted[n / 2] as f64) / 2.0
+        } else {
+            sorted[n / 2] as f64
+        };
+        Summary {
+            min: sorted[0],
+            max: sorted[n - 1],
+            mean: sorted.iter().map(|&v| v as f64).sum::<f64>() / n as f64,
+            median,
+        }
+    }
+}
+
+impl Report {
+    fn new(elves: &[Elf], buckets: usize) -> Self {
+        let mut calories: Vec<u64> = elves.iter().map(|elf| elf.calories).collect();
+        calories.sort_unstable();
+        let mut items: Vec<u64> = elves.iter().map(|elf| elf.items.len() as u64).collect();
+        items.sort_unstable();
+
+        let percentiles = if calories.is_empty() {
+            Vec::new()
+        } else {
+            PERCENTILES
+                .iter()
+                .map(|&p| {
+                    let rank = (p * calories.len()).div_ceil(100).max(1);
+                    (p, calories[rank - 1])
+                })
+                .collect()
+        };
 
-    println!("Solution 01: {}", elf_list[0]);
-    println!("Solution 02: {}", elf_list[0] + elf_list[1] + elf_list[2]);
+        let mut ranked: Vec<&Elf> = elves.iter().collect();
+        ranked.sort_by_key(|elf| (Reverse(elf.calories), elf.index));
+        let ranks = ranked
+            .into_iter()
+            .map(|elf| Rank {
+                index: elf.index,
+                name: elf.name.clone(),
+                calories: elf.calories,
+                percentile: percentile_rank(&calories, elf.calories),
+            })
+            .collect();
+
+        Report {
+            count: elves.len(),
+            calories: Summary::new(&calories),
+            items: Summary::new(&items),
+            percentiles,
+            ranks,
+            histogram: histogram(&calories, buckets.max(1)),
+        }
+    }
+
+    fn to_table(&self) -> String {
+        let mut table = String::new();
+        let summary = |table: &mut String, name: &str, s: &Summary| {
+            writeln!(
+                table,
+                "{name:<10} min {:<8} max {:<8} mean {:<10.1} median {:.1}",
+                s.min, s.max, s.mean, s.median
+            )
+            .unwrap();
+        };
+        writeln!(table, "{:<10} {}", "elves", self.count).unwrap();
+        summary(&mut table, "calories", &self.calories);
+        summary(&mut table, "items", &self.items);
+
+        writeln!(table, "\npercentiles").unwrap();
+        for (p, calories) in &self.percentiles {
+            writeln!(table, "  p{p:<3} {calories}").unwrap();
+        }
+
+        writeln!(table, "\npercentile ranks").unwrap();
+        for (place, rank) in self.ranks.iter().take(TABLE_RANKS).enumerate() {
+            let name = rank.name.as_deref().unwrap_or("");
+            let line = format!(
+                "  {:>2}. elf {:<6} {:>8} {:>6.1}%  {name}",
+                place + 1,
+                rank.index,
+                rank.calories,
+                rank.percentile
+            );
+            writeln!(table, "{}", line.trim_end()).unwrap();
+        }
+        if self.ranks.len() > TABLE_RANKS {
+            writeln!(table, "  ... {} more", self.ranks.len() - TABLE_RANKS).unwrap();
+        }
+
+        writeln!(table, "\nhistogram").unwrap();
+        let widest = self.histogram.iter().map(|b| b.count).max().unwrap_or(0);
+        for bucket in &self.histogram {
+            let bar = (bucket.count * 40).checked_div(widest).unwrap_or(0);
+            let line = format!(
+                "  {:>8}-{:<8} {:>6} {}",
+                bucket.from,
+                bucket.to,
+                bucket.count,
+                "#".repeat(bar)
+            );
+            writeln!(table, "{}", line.trim_end()).unwrap();
+        }
+        table
+    }
+
+    fn to_json(&self) -> String {
+        let summary =
+            |s: &Summary| json!({"min": s.min, "max": s.max, "mean": s.mean, "median": s.median});
+        let percentiles: Map<String, Value> = self
+            .percentiles
+            .iter()
+            .map(|(p, calories)| (format!("p{p}"), json!(calories)))
+            .collect();
+        let histogram: Vec<_> = self
+            .histogram
+            .iter()
+            .map(|b| json!({"from": b.from, "to": b.to, "count": b.count}))
+            .collect();
+        let ranks: Vec<_> = self
+            .ranks
+            .iter()
+            .map(|r| {
+                json!({
+                    "elf": r.index,
+                    "name": r.name,
+                    "calories": r.calories,
+                    "percentile_rank": r.percentile,
+                })
+            })
+            .collect();
+        let report = json!({
+            "elves": self.count,
+            "calories": summary(&self.calories),
+            "items": summary(&self.items),
+            "percentiles": percentiles,
+            "ranks": ranks,
+            "histogram": histogram,
+        });
+        serde_json::to_string_pretty(&report).unwrap()
+    }
+}
+
+/// The percentage of the sorted values below `value`, counting the values equal to it by half.
+fn percentile_rank(sorted: &[u64], value: u64) -> f64 {
+    let below = sorted.partition_point(|&v| v < value);
+    let equal = sorted.partition_point(|&v| v <= value) - below;
+    (below as f64 + equal as f64 / 2.0) * 100.0 / sorted.len() as f64
+}
+
+/// Divides the range of the sorted values into `buckets` buckets of equal width, the last bucket
+/// ends at the largest value.
+fn histogram(sorted: &[u64], buckets: usize) -> Vec<Bucket> {
+    let (Some(&min), Some(&max)) = (sorted.first(), sorted.last()) else {
+        return Vec::new();
+    };
+    let width = ((max - min) / buckets as u64 + 1).max(1);
+    let mut histogram: Vec<Bucket> = (0..buckets as u64)
+        .map(|i| Bucket {
+            from: min.saturating_add(i * width),
+            to: min.saturating_add(i * width + width - 1).min(max),
+            count: 0,
+        })
+        .take_while(|bucket| bucket.from <= max)
+        .collect();
+    for &value in sorted {
+        histogram[((value - min) / width) as usize].count += 1;
+    }
+    histogram
+}
+
+/// Returns the fewest elves that together carry at least `target` calories, or `None` if all
+/// elves together carry less. Taking the elves with the most calories first is optimal.
+fn smallest_covering_set(elves: &[Elf], target: u64) -> Option<Vec<Elf>> {
+    if target == 0 {
+        return Some(Vec::new());
+    }
+    let mut sorted = elves.to_vec();
+    sorted.sort();
+    let mut calories = 0;
+    let count = sorted
+        .iter()
+        .position(|elf| {
+            calories = checked::add(calories, elf.calories);
+            calories >= target
+        })
+        .map(|position| position + 1)?;
+    sorted.truncate(count);
+    Some(sorted)
+}
+
+/// Default number of steps [`exact_subset`] may take before it settles for an approximation.
+const SEARCH_BUDGET: usize = 1_000_000;
+
+/// Inventories with more elves than this are only approximated, the search recurses per elf.
+const SEARCH_MAX_ELVES: usize = 10_000;
+
+/// The result of searching for elves that together carry exactly a number of calories.
+#[derive(Debug, PartialEq)]
+enum Subset {
+    Exact(Vec<Elf>),
+    /// No combination of elves carries exactly the target.
+    Impossible,
+    /// The search ran out of budget, these elves come closest without exceeding the target.
+    Approximation(Vec<Elf>),
+}
+
+/// Searches for elves that together carry exactly `target` calories with a depth-first subset-sum
+/// search over the elves sorted by calories. Branches that cannot reach the target with the
+/// remaining elves, or overshoot it, are pruned. Large inventories may need more than `budget`
+/// steps, in which case the best combination found so far is returned.
+fn exact_subset(elves: &[Elf], target: u64, budget: usize) -> Subset {
+    if elves.len() > SEARCH_MAX_ELVES {
+        return greedy_subset(elves, target);
+    }
+
+    struct Search {
+        elves: Vec<Elf>,
+        /// `remaining[i]` is the total of `elves[i..]`.
+        remaining: Vec<u64>,
+        target: u64,
+        budget: usize,
+        chosen: Vec<usize>,
+        best: (u64, Vec<usize>),
+    }
+
+    impl Search {
+        fn run(&mut self, i: usize, calories: u64) -> bool {
+            if calories == self.target {
+                return true;
+            }
+            if calories > self.best.0 {
+                self.best = (calories, self.chosen.clone());
+            }
+            if self.budget == 0
+                || i == self.elves.len()
+                || checked::add(calories, self.remaining[i]) < self.target
+            {
+                return false;
+            }
+            self.budget -= 1;
+
+            let with = checked::add(calories, self.elves[i].calories);
+            if with <= self.target {
+                self.chosen.push(i);
+                if self.run(i + 1, with) {
+                    return true;
+                }
+                self.chosen.pop();
+            }
+
+            // Leaving out elf `i` also leaves out the elves with the same calories after it, any
+            // combination with those was already tried with elf `i` in its place.
+            let next = (i + 1..self.elves.len())
+                .find(|&j| self.elves[j].calories != self.elves[i].calories)
+                .unwrap_or(self.elves.len());
+            self.run(next, calories)
+        }
+    }
+
+    let mut sorted = elves.to_vec();
+    sorted.sort();
+    let mut remaining = vec![0; sorted.len() + 1];
+    for i in (0..sorted.len()).rev() {
+        remaining[i] = checked::add(remaining[i + 1], sorted[i].calories);
+    }
+    let mut search = Search {
+        elves: sorted,
+        remaining,
+        target,
+        budget,
+        chosen: Vec::new(),
+        best: (0, Vec::new()),
+    };
+    let found = search.run(0, 0);
+    let pick = |indices: &[usize]| indices.iter().map(|&i| search.elves[i].clone()).collect();
+    if found {
+        Subset::Exact(pick(&search.chosen))
+    } else if search.budget == 0 {
+        Subset::Approximation(pick(&search.best.1))
+    } else {
+        Subset::Impossible
+    }
+}
+
+/// Approximates [`exact_subset`] by taking every elf that still fits, most calories first.
+fn greedy_subset(elves: &[Elf], target: u64) -> Subset {
+    let mut sorted = elves.to_vec();
+    sorted.sort();
+    let mut calories = 0;
+    sorted.retain(|elf| {
+        let with = checked::add(calories, elf.calories);
+        let fits = with <= target;
+        if fits {
+            calories = with;
+        }
+        fits
+    });
+    if calories == target {
+        Subset::Exact(sorted)
+    } else {
+        Subset::Approximation(sorted)
+    }
+}
+
+fn describe(elves: &[Elf]) -> String {
+    let indices: Vec<_> = elves.iter().map(|elf| elf.index.to_string()).collect();
+    format!(
+        "elves {} ({} calories)",
+        indices.join(", "),
+        total_calories(elves)
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        exact_subset, greedy_subset, histogram, percentile_rank, read_elves, smallest_covering_set,
+        solve, top_elves, Bucket, Elf, InventoryFormat, Item, Report, Subset,
+    };
+    use serde_json::{json, Value};
+
+    const EXAMPLE: &str = "1000\n2000\n3000\n\n4000\n\n5000\n6000\n\n7000\n8000\n9000\n\n10000";
+
+    /// Returns the index, calories and number of items of every elf.
+    fn summary(elves: &[Elf]) -> Vec<(usize, u64, usize)> {
+        elves
+            .iter()
+            .map(|elf| (elf.index, elf.calories, elf.items.len()))
+            .collect()
+    }
+
+    fn top_plain_elves(input: &str, n: usize) -> std::io::Result<Vec<Elf>> {
+        top_elves(input.as_bytes(), InventoryFormat::Plain, n)
+    }
+
+    #[test]
+    fn test_top_elves() {
+        assert_eq!(
+            summary(&top_plain_elves(EXAMPLE, 3).unwrap()),
+            vec![(3, 24000, 3), (2, 11000, 2), (4, 10000, 1)]
+        );
+    }
+
+    #[test]
+    fn test_top_elves_flushes_last_group() {
+        let elves = top_plain_elves("1\n\n2\n3", 1).unwrap();
+        assert_eq!(summary(&elves), vec![(1, 5, 2)]);
+        assert_eq!(top_plain_elves("1\n\n\n2\n", 5).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_empty_inventory() {
+        assert_eq!(solve(""), ["0", "0"]);
+        assert_eq!(solve("\n\n"), ["0", "0"]);
+        assert_eq!(solve("7"), ["7", "7"]);
+    }
+
+    #[test]
+    fn test_top_elves_ties_and_errors() {
+        let elves = top_plain_elves("5\n\n5\n\n5", 2).unwrap();
+        assert_eq!(
+            elves.iter().map(|elf| elf.index).collect::<Vec<_>>(),
+            vec![0, 1]
+        );
+        let error = top_plain_elves("1\n\nabc", 2).unwrap_err();
+        assert!(error.to_string().starts_with("line 3:"));
+    }
+
+    #[test]
+    fn test_csv_inventory() {
+        let csv = "elf,item,calories\n\
+                   Ann,apple,1000\n\
+                   Bob,\"bread, rye\",3000\n\
+                   Ann,pear,2500\n";
+        let elves = top_elves(csv.as_bytes(), InventoryFormat::Csv, 2).unwrap();
+        assert_eq!(summary(&elves), vec![(0, 3500, 2), (1, 3000, 1)]);
+        assert_eq!(elves[0].name.as_deref(), Some("Ann"));
+        assert_eq!(
+            elves[1].items,
+            vec![Item {
+                name: Some("bread, rye".to_owned()),
+                calories: 3000
+            }]
+        );
+        let error = top_elves("Ann,apple\n".as_bytes(), InventoryFormat::Csv, 2).unwrap_err();
+        assert_eq!(error.to_string(), "line 1: expected 3 fields, found 2");
+        // Only a literal header is skipped, a bad first row is an error.
+        let headed = "Elf,Item,Calories\nAnn,apple,10\n";
+        let elves = top_elves(headed.as_bytes(), InventoryFormat::Csv, 1).unwrap();
+        assert_eq!(summary(&elves), vec![(0, 10, 1)]);
+        let bad = "Ann,apple,10x0\nAnn,pear,5\n";
+        let error = top_elves(bad.as_bytes(), InventoryFormat::Csv, 1).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("line 1: invalid calories \"10x0\""));
+    }
+
+    #[test]
+    fn test_json_inventory() {
+        let json = r#"[
+            {"elf": "Ann", "items": [{"name": "apple", "calories": 1000}, 500]},
+            {"elf": "Bob", "items": [{"name": "bread", "calories": 3000}]}
+        ]"#;
+        let elves = top_elves(json.as_bytes(), InventoryFormat::Json, 2).unwrap();
+        assert_eq!(summary(&elves), vec![(1, 3000, 1), (0, 1500, 2)]);
+        assert_eq!(elves[1].items[0].name.as_deref(), Some("apple"));
+        assert_eq!(elves[1].items[1].name, None);
+        assert!(top_elves("{}".as_bytes(), InventoryFormat::Json, 2).is_err());
+
+        // Calories beyond 2^53 are kept exactly, fractions and values beyond `u64` are rejected.
+        let json = r#"[{"items": [9007199254740993]}]"#;
+        let elves = top_elves(json.as_bytes(), InventoryFormat::Json, 1).unwrap();
+        assert_eq!(elves[0].calories, 9007199254740993);
+        for json in [
+            r#"[{"items": [1.5]}]"#,
+            r#"[{"items": [18446744073709551616]}]"#,
+        ] {
+            let error = top_elves(json.as_bytes(), InventoryFormat::Json, 1).unwrap_err();
+            assert!(error.to_string().starts_with("elf 0: invalid calories"));
+        }
+    }
+
+    #[test]
+    fn test_report() {
+        let mut elves = Vec::new();
+        read_elves(EXAMPLE.as_bytes(), InventoryFormat::Plain, |elf| {
+            elves.push(elf)
+        })
+        .unwrap();
+        let report = Report::new(&elves, 2);
+        assert_eq!(report.count, 5);
+        assert_eq!(report.calories.min, 4000);
+        assert_eq!(report.calories.max, 24000);
+        assert_eq!(report.calories.mean, 11000.0);
+        assert_eq!(report.calories.median, 10000.0);
+        assert_eq!(report.items.mean, 2.0);
+        assert_eq!(report.items.median, 2.0);
+        assert!(report.percentiles.contains(&(50, 10000)));
+        assert!(report.percentiles.contains(&(99, 24000)));
+        let ranks: Vec<_> = report
+            .ranks
+            .iter()
+            .map(|r| (r.index, r.calories, r.percentile))
+            .collect();
+        assert_eq!(
+            ranks,
+            [
+                (3, 24000, 90.0),
+                (2, 11000, 70.0),
+                (4, 10000, 50.0),
+                (0, 6000, 30.0),
+                (1, 4000, 10.0)
+            ]
+        );
+        assert_eq!(
+            report.histogram,
+            vec![
+                Bucket {
+                    from: 4000,
+                    to: 14000,
+                    count: 4
+                },
+                Bucket {
+                    from: 14001,
+                    to: 24000,
+                    count: 1
+                }
+            ]
+        );
+        let json: Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["elves"], 5);
+        assert_eq!(
+            json["calories"],
+            json!({"min": 4000, "max": 24000, "mean": 11000.0, "median": 10000.0})
+        );
+        assert_eq!(json["percentiles"]["p50"], 10000);
+        assert_eq!(
+            json["ranks"][0],
+            json!({"elf": 3, "name": null, "calories": 24000, "percentile_rank": 90.0})
+        );
+        assert_eq!(
+            json["histogram"][1],
+            json!({"from": 14001, "to": 24000, "count": 1})
+        );
+    }
+
+    #[test]
+    fn test_percentile_rank() {
+        assert_eq!(percentile_rank(&[5, 5, 5, 5], 5), 50.0);
+        assert_eq!(percentile_rank(&[1, 2, 2, 3], 2), 50.0);
+        assert_eq!(percentile_rank(&[1, 2, 2, 3], 3), 87.5);
+        assert_eq!(percentile_rank(&[7], 7), 50.0);
+    }
+
+    #[test]
+    fn test_histogram() {
+        assert_eq!(histogram(&[], 3), vec![]);
+        let buckets = histogram(&[0, 10], 3);
+        assert_eq!(
+            buckets.last().map(|b| (b.from, b.to, b.count)),
+            Some((8, 10, 1))
+        );
+        assert_eq!(
+            histogram(&[5, 5], 3),
+            vec![Bucket {
+                from: 5,
+                to: 5,
+                count: 2
+            }]
+        );
+    }
+
+    fn elves(calories: &[u64]) -> Vec<Elf> {
+        calories
+            .iter()
+            .enumerate()
+            .map(|(index, &calories)| {
+                let mut elf = Elf::new(index, None);
+                elf.add(Item {
+                    name: None,
+                    calories,
+                });
+                elf
+            })
+            .collect()
+    }
+
+    fn indices(elves: &[Elf]) -> Vec<usize> {
+        let mut indices: Vec<_> = elves.iter().map(|elf| elf.index).collect();
+        indices.sort();
+        indices
+    }
+
+    #[test]
+    fn test_smallest_covering_set() {
+        let elves = elves(&[6000, 4000, 11000, 24000, 10000]);
+        assert_eq!(
+            indices(&smallest_covering_set(&elves, 24000).unwrap()),
+            vec![3]
+        );
+        assert_eq!(
+            indices(&smallest_covering_set(&elves, 35001).unwrap()),
+            vec![2, 3, 4]
+        );
+        assert_eq!(smallest_covering_set(&elves, 55001), None);
+        assert_eq!(smallest_covering_set(&elves, 0), Some(vec![]));
+    }
+
+    #[test]
+    fn test_exact_subset() {
+        let elves = elves(&[6000, 4000, 11000, 24000, 10000]);
+        match exact_subset(&elves, 20000, 1000) {
+            Subset::Exact(found) => {
+                assert_eq!(found.iter().map(|elf| elf.calories).sum::<u64>(), 20000)
+            }
+            other => panic!("expected an exact subset, got {other:?}"),
+        }
+        assert_eq!(exact_subset(&elves, 5000, 1000), Subset::Impossible);
+        assert_eq!(exact_subset(&elves, 0, 1000), Subset::Exact(vec![]));
+
+        let many = self::elves(&[3, 3, 3, 3, 3, 3, 3, 3, 3, 3]);
+        assert_eq!(exact_subset(&many, 29, 1000), Subset::Impossible);
+        assert_eq!(exact_subset(&many, 31, 1000), Subset::Impossible);
+        match exact_subset(&many, 29, 2) {
+            Subset::Approximation(found) => assert!(!found.is_empty()),
+            other => panic!("expected an approximation, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_greedy_subset() {
+        let elves = elves(&[5, 4, 3]);
+        assert_eq!(
+            indices(&match greedy_subset(&elves, 9) {
+                Subset::Exact(found) => found,
+                other => panic!("expected an exact subset, got {other:?}"),
+            }),
+            vec![0, 1]
+        );
+        match greedy_subset(&elves, 7) {
+            Subset::Approximation(found) => assert_eq!(indices(&found), vec![0]),
+            other => panic!("expected an approximation, got {other:?}"),
+        }
+    }
 }
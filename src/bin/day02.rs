@@ -1,27 +1,33 @@
-use std::path::Path;
-#[derive(PartialEq, Debug, Clone, Copy)]
-enum Shape {
-    Rock = 1,
-    Paper = 2,
-    Scissor = 3,
-}
-#[derive(Debug)]
+use aoc_2022::batch;
+use aoc_2022::config::{Config, OutputFormat};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use toml::{Table, Value};
+
+/// A shape in a [`Rules`] table, identified by its position in the table.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+struct Shape(usize);
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
 enum GameEnd {
-    Win = 6,
-    Lose = 0,
-    Draw = 3,
+    Win,
+    Lose,
+    Draw,
 }
 
 struct Hand {
     shape: Shape,
-    wins_from: Shape,
-    looses_from: Shape,
+    name: String,
+    score: usize,
+    wins_from: Vec<Shape>,
+    looses_from: Vec<Shape>,
 }
 impl Hand {
     fn outcome(&self, opponent: &Hand) -> GameEnd {
         if self.shape == opponent.shape {
             GameEnd::Draw
-        } else if self.looses_from == opponent.shape {
+        } else if self.looses_from.contains(&opponent.shape) {
             GameEnd::Lose
         } else {
             GameEnd::Win
@@ -29,65 +35,1331 @@ impl Hand {
     }
 }
 
-/// Part two needs the values to be matched to GameEnd.
-fn match_end(end: &str) -> Option<GameEnd> {
+/// The shapes of a cyclic game, which shape beats which, and how rounds are scored.
+///
+/// Rule files use the TOML format, e.g. the default rules are:
+///
+/// ```toml
+/// shapes = ["rock", "paper", "scissors"]
+///
+/// [scores]
+/// rock = 1
+/// paper = 2
+/// scissors = 3
+///
+/// [beats]
+/// rock = ["scissors"]
+/// paper = ["rock"]
+/// scissors = ["paper"]
+///
+/// # The letters a shape is written as in the strategy guide.
+/// [codes]
+/// rock = ["A", "X"]
+/// paper = ["B", "Y"]
+/// scissors = ["C", "Z"]
+///
+/// [outcomes]
+/// lose = 0
+/// draw = 3
+/// win = 6
+///
+/// # The letters an outcome is written as in part two.
+/// [outcome_codes]
+/// lose = "X"
+/// draw = "Y"
+/// win = "Z"
+/// ```
+struct Rules {
+    hands: Vec<Hand>,
+    codes: HashMap<String, Shape>,
+    outcome_scores: HashMap<GameEnd, usize>,
+    outcome_codes: HashMap<String, GameEnd>,
+}
+
+const ROCK_PAPER_SCISSORS: &str = r#"
+shapes = ["rock", "paper", "scissors"]
+
+[scores]
+rock = 1
+paper = 2
+scissors = 3
+
+[beats]
+rock = ["scissors"]
+paper = ["rock"]
+scissors = ["paper"]
+
+[codes]
+rock = ["A", "X"]
+paper = ["B", "Y"]
+scissors = ["C", "Z"]
+
+[outcomes]
+lose = 0
+draw = 3
+win = 6
+
+[outcome_codes]
+lose = "X"
+draw = "Y"
+win = "Z"
+"#;
+
+/// Why a rule table was rejected.
+#[derive(Debug, PartialEq, Eq)]
+enum RulesError {
+    Parse(toml::de::Error),
+    /// A required setting is missing or has the wrong type.
+    Invalid(String),
+    UnknownShape(String),
+    /// A shape is listed as beating itself.
+    Reflexive(String),
+    /// Both shapes are listed as beating each other.
+    Asymmetric(String, String),
+    /// Neither shape beats the other.
+    Incomplete(String, String),
+    /// A guide letter is used for more than one shape.
+    DuplicateCode(String),
+    /// A game needs at least two shapes to have a winner.
+    TooFewShapes(usize),
+    /// A shape beats no other shape, so part 2 cannot make it win.
+    NeverWins(String),
+    /// No other shape beats this one, so part 2 cannot make it lose.
+    NeverLoses(String),
+}
+
+impl Display for RulesError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RulesError::Parse(e) => write!(f, "{e}"),
+            RulesError::Invalid(message) => write!(f, "{message}"),
+            RulesError::UnknownShape(name) => write!(f, "unknown shape `{name}`"),
+            RulesError::Reflexive(name) => write!(f, "`{name}` cannot beat itself"),
+            RulesError::Asymmetric(a, b) => write!(f, "`{a}` and `{b}` both beat each other"),
+            RulesError::Incomplete(a, b) => write!(f, "neither `{a}` nor `{b}` beats the other"),
+            RulesError::DuplicateCode(code) => write!(f, "code `{code}` is used more than once"),
+            RulesError::TooFewShapes(count) => {
+                write!(f, "need at least two shapes, found {count}")
+            }
+            RulesError::NeverWins(name) => write!(f, "`{name}` does not beat any shape"),
+            RulesError::NeverLoses(name) => write!(f, "no shape beats `{name}`"),
+        }
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules::parse(ROCK_PAPER_SCISSORS).unwrap()
+    }
+}
+
+impl Rules {
+    fn parse(text: &str) -> Result<Self, RulesError> {
+        let document: Table = text.parse().map_err(RulesError::Parse)?;
+        let invalid = |message: &str| RulesError::Invalid(message.to_owned());
+        let section = |name: &str| {
+            document
+                .get(name)
+                .and_then(Value::as_table)
+                .ok_or_else(|| RulesError::Invalid(format!("missing section [{name}]")))
+        };
+
+        let names = match document.get("shapes") {
+            Some(Value::Array(values)) => values
+                .iter()
+                .map(|value| match value {
+                    Value::String(name) => Ok(name.clone()),
+                    _ => Err(invalid("`shapes` must be a list of names")),
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            _ => return Err(invalid("missing list of `shapes`")),
+        };
+        if names.len() < 2 {
+            return Err(RulesError::TooFewShapes(names.len()));
+        }
+        let shape = |name: &str| {
+            names
+                .iter()
+                .position(|n| n == name)
+                .map(Shape)
+                .ok_or_else(|| RulesError::UnknownShape(name.to_owned()))
+        };
+        let strings = |value: &Value, key: &str| match value {
+            Value::Array(values) => values
+                .iter()
+                .map(|value| match value {
+                    Value::String(s) => Ok(s.clone()),
+                    _ => Err(RulesError::Invalid(format!(
+                        "`{key}` must be a list of names"
+                    ))),
+                })
+                .collect::<Result<Vec<_>, _>>(),
+            _ => Err(RulesError::Invalid(format!(
+                "`{key}` must be a list of names"
+            ))),
+        };
+        let number = |value: &Value, key: &str| match value {
+            Value::Integer(i) if *i >= 0 => Ok(*i as usize),
+            _ => Err(RulesError::Invalid(format!("`{key}` must be a score"))),
+        };
+
+        let mut hands: Vec<Hand> = names
+            .iter()
+            .enumerate()
+            .map(|(idx, name)| Hand {
+                shape: Shape(idx),
+                name: name.clone(),
+                score: 0,
+                wins_from: Vec::new(),
+                looses_from: Vec::new(),
+            })
+            .collect();
+
+        let scores = section("scores")?;
+        for (name, value) in scores {
+            hands[shape(name)?.0].score = number(value, name)?;
+        }
+        if let Some(hand) = hands.iter().find(|hand| !scores.contains_key(&hand.name)) {
+            return Err(RulesError::Invalid(format!(
+                "missing score of `{}`",
+                hand.name
+            )));
+        }
+
+        for (name, value) in section("beats")? {
+            let winner = shape(name)?;
+            for loser in strings(value, name)? {
+                let loser = shape(&loser)?;
+                if winner == loser {
+                    return Err(RulesError::Reflexive(name.clone()));
+                }
+                hands[winner.0].wins_from.push(loser);
+                hands[loser.0].looses_from.push(winner);
+            }
+        }
+        for hand in &mut hands {
+            hand.wins_from.sort_by_key(|shape| shape.0);
+            hand.looses_from.sort_by_key(|shape| shape.0);
+        }
+        for a in &hands {
+            for b in hands.iter().skip(a.shape.0 + 1) {
+                match (
+                    a.wins_from.contains(&b.shape),
+                    a.looses_from.contains(&b.shape),
+                ) {
+                    (true, true) => {
+                        return Err(RulesError::Asymmetric(a.name.clone(), b.name.clone()))
+                    }
+                    (false, false) => {
+                        return Err(RulesError::Incomplete(a.name.clone(), b.name.clone()))
+                    }
+                    _ => {}
+                }
+            }
+        }
+        for hand in &hands {
+            if hand.wins_from.is_empty() {
+                return Err(RulesError::NeverWins(hand.name.clone()));
+            }
+            if hand.looses_from.is_empty() {
+                return Err(RulesError::NeverLoses(hand.name.clone()));
+            }
+        }
+
+        let mut codes = HashMap::new();
+        for (name, value) in section("codes")? {
+            let shape = shape(name)?;
+            for code in strings(value, name)? {
+                if codes.insert(code.clone(), shape).is_some() {
+                    return Err(RulesError::DuplicateCode(code));
+                }
+            }
+        }
+
+        let end = |name: &str| match name {
+            "win" => Ok(GameEnd::Win),
+            "draw" => Ok(GameEnd::Draw),
+            "lose" => Ok(GameEnd::Lose),
+            _ => Err(RulesError::Invalid(format!("unknown outcome `{name}`"))),
+        };
+        let mut outcome_scores = HashMap::new();
+        for (name, value) in section("outcomes")? {
+            outcome_scores.insert(end(name)?, number(value, name)?);
+        }
+        if outcome_scores.len() != 3 {
+            return Err(invalid("[outcomes] needs a score for win, draw and lose"));
+        }
+        let mut outcome_codes = HashMap::new();
+        for (name, value) in section("outcome_codes")? {
+            let Value::String(code) = value else {
+                return Err(RulesError::Invalid(format!("`{name}` must be a code")));
+            };
+            if outcome_codes.insert(code.clone(), end(name)?).is_some() {
+                return Err(RulesError::DuplicateCode(code.clone()));
+            }
+        }
+
+        Ok(Rules {
+            hands,
+            codes,
+            outcome_scores,
+            outcome_codes,
+        })
+    }
+
+    fn hand(&self, shape: Shape) -> &Hand {
+        &self.hands[shape.0]
+    }
+
+    fn match_hand(&self, code: &str) -> Option<&Hand> {
+        self.codes.get(code).map(|&shape| self.hand(shape))
+    }
+
+    /// Part two needs the values to be matched to GameEnd.
+    fn match_end(&self, code: &str) -> Option<GameEnd> {
+        self.outcome_codes.get(code).copied()
+    }
+
+    fn score(&self, hand: &Hand, end: GameEnd) -> usize {
+        hand.score + self.outcome_scores[&end]
+    }
+
+    /// The hand to play against `opponent` to get `end`. If several shapes give that outcome the
+    /// first one in the table is played.
+    fn hand_for<'a>(&'a self, opponent: &'a Hand, end: GameEnd) -> &'a Hand {
+        match end {
+            GameEnd::Draw => opponent,
+            GameEnd::Win => self.hand(opponent.looses_from[0]),
+            GameEnd::Lose => self.hand(opponent.wins_from[0]),
+        }
+    }
+}
+
+/// One round of the strategy guide as seen by a [`GuideInterpretation`].
+struct Round<'a> {
+    opponent: &'a Hand,
+    /// The code in the second column.
+    code: &'a str,
+    /// The opponent's hand in the round before, if any.
+    previous: Option<&'a Hand>,
+}
+
+/// A way to read the second column of the strategy guide.
+trait GuideInterpretation {
+    fn name(&self, rules: &Rules) -> String;
+
+    /// Returns the hand to play in a round, `None` if the code means nothing to it.
+    fn play<'a>(&self, rules: &'a Rules, round: &Round<'a>) -> Option<&'a Hand>;
+}
+
+/// Part one: the code is a shape.
+struct AsShape;
+
+impl GuideInterpretation for AsShape {
+    fn name(&self, _rules: &Rules) -> String {
+        "shape".to_owned()
+    }
+
+    fn play<'a>(&self, rules: &'a Rules, round: &Round<'a>) -> Option<&'a Hand> {
+        rules.match_hand(round.code)
+    }
+}
+
+/// Part two: the code is the outcome of the round.
+struct AsOutcome;
+
+impl GuideInterpretation for AsOutcome {
+    fn name(&self, _rules: &Rules) -> String {
+        "outcome".to_owned()
+    }
+
+    fn play<'a>(&self, rules: &'a Rules, round: &Round<'a>) -> Option<&'a Hand> {
+        let end = rules.match_end(round.code)?;
+        Some(rules.hand_for(round.opponent, end))
+    }
+}
+
+/// Plays what beats the opponent's previous hand, the first round is read as a shape.
+struct BeatPrevious;
+
+impl GuideInterpretation for BeatPrevious {
+    fn name(&self, _rules: &Rules) -> String {
+        "beat-previous".to_owned()
+    }
+
+    fn play<'a>(&self, rules: &'a Rules, round: &Round<'a>) -> Option<&'a Hand> {
+        match round.previous {
+            Some(previous) => Some(rules.hand_for(previous, GameEnd::Win)),
+            None => AsShape.play(rules, round),
+        }
+    }
+}
+
+/// Plays the shape a number of places after the opponent's shape in the rule table.
+struct Rotate {
+    offsets: Vec<(String, usize)>,
+}
+
+impl GuideInterpretation for Rotate {
+    fn name(&self, _rules: &Rules) -> String {
+        let offsets: Vec<_> = self
+            .offsets
+            .iter()
+            .map(|(code, offset)| format!("{code}={offset}"))
+            .collect();
+        format!("rotate:{}", offsets.join(","))
+    }
+
+    fn play<'a>(&self, rules: &'a Rules, round: &Round<'a>) -> Option<&'a Hand> {
+        let (_, offset) = self.offsets.iter().find(|(code, _)| code == round.code)?;
+        let shape = Shape((round.opponent.shape.0 + offset) % rules.hands.len());
+        Some(rules.hand(shape))
+    }
+}
+
+/// What a code stands for in a [`Mapping`].
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+enum Response {
+    Shape(Shape),
+    End(GameEnd),
+}
+
+/// Reads every code as either a shape or an outcome.
+struct Mapping {
+    responses: Vec<(String, Response)>,
+}
+
+impl GuideInterpretation for Mapping {
+    fn name(&self, rules: &Rules) -> String {
+        format!("map:{}", self.describe(rules))
+    }
+
+    fn play<'a>(&self, rules: &'a Rules, round: &Round<'a>) -> Option<&'a Hand> {
+        let (_, response) = self.responses.iter().find(|(code, _)| code == round.code)?;
+        match *response {
+            Response::Shape(shape) => Some(rules.hand(shape)),
+            Response::End(end) => Some(rules.hand_for(round.opponent, end)),
+        }
+    }
+}
+
+impl Mapping {
+    /// Describes the mapping as `X=rock,Y=draw,...`.
+    fn describe(&self, rules: &Rules) -> String {
+        let responses: Vec<_> = self
+            .responses
+            .iter()
+            .map(|(code, response)| match response {
+                Response::Shape(shape) => format!("{code}={}", rules.hand(*shape).name),
+                Response::End(end) => format!("{code}={}", end_name(*end)),
+            })
+            .collect();
+        responses.join(",")
+    }
+}
+
+fn end_name(end: GameEnd) -> &'static str {
     match end {
-        "X" => Some(GameEnd::Lose),
-        "Y" => Some(GameEnd::Draw),
-        "Z" => Some(GameEnd::Win),
-        _ => None,
+        GameEnd::Win => "win",
+        GameEnd::Draw => "draw",
+        GameEnd::Lose => "lose",
     }
 }
-fn main() {
-    // Read input file
-    let day = Path::new(file!()).file_stem().unwrap().to_str().unwrap();
-    let input = std::fs::read_to_string(format!("inputs/{day}.txt")).unwrap();
 
-    let rock = Hand {
-        shape: Shape::Rock,
-        wins_from: Shape::Scissor,
-        looses_from: Shape::Paper,
-    };
-    let paper = Hand {
-        shape: Shape::Paper,
-        wins_from: Shape::Rock,
-        looses_from: Shape::Scissor,
+/// Parses an interpretation from the command line:
+///
+/// - `shape`, `outcome` and `beat-previous` select the built-in interpretations,
+/// - `rotate` or `rotate:X=0,Y=1,Z=2` plays the shape that many places after the opponent's,
+/// - `map:X=rock,Y=draw,Z=win` maps each code to a shape or an outcome.
+fn parse_interpretation(spec: &str, rules: &Rules) -> Result<Box<dyn GuideInterpretation>, String> {
+    let (kind, arguments) = spec.split_once(':').unwrap_or((spec, ""));
+    let pairs = || {
+        arguments
+            .split(',')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                pair.split_once('=')
+                    .map(|(code, value)| (code.trim().to_owned(), value.trim()))
+                    .ok_or_else(|| format!("expected `code=value`, found `{pair}`"))
+            })
+            .collect::<Result<Vec<_>, _>>()
     };
-    let scissor = Hand {
-        shape: Shape::Scissor,
-        wins_from: Shape::Paper,
-        looses_from: Shape::Rock,
+    match kind {
+        "shape" => Ok(Box::new(AsShape)),
+        "outcome" => Ok(Box::new(AsOutcome)),
+        "beat-previous" => Ok(Box::new(BeatPrevious)),
+        "rotate" if arguments.is_empty() => Ok(Box::new(Rotate {
+            offsets: vec![
+                ("X".to_owned(), 0),
+                ("Y".to_owned(), 1),
+                ("Z".to_owned(), 2),
+            ],
+        })),
+        "rotate" => {
+            let offsets = pairs()?
+                .into_iter()
+                .map(|(code, offset)| match offset.parse() {
+                    Ok(offset) => Ok((code, offset)),
+                    Err(_) => Err(format!("invalid offset `{offset}`")),
+                })
+                .collect::<Result<_, _>>()?;
+            Ok(Box::new(Rotate { offsets }))
+        }
+        "map" => {
+            let responses = pairs()?
+                .into_iter()
+                .map(|(code, value)| {
+                    let response = match value {
+                        "win" => Response::End(GameEnd::Win),
+                        "draw" => Response::End(GameEnd::Draw),
+                        "lose" => Response::End(GameEnd::Lose),
+                        name => rules
+                            .hands
+                            .iter()
+                            .find(|hand| hand.name == name)
+                            .map(|hand| Response::Shape(hand.shape))
+                            .ok_or_else(|| format!("unknown shape or outcome `{name}`"))?,
+                    };
+                    Ok((code, response))
+                })
+                .collect::<Result<_, String>>()?;
+            Ok(Box::new(Mapping { responses }))
+        }
+        _ => Err(format!("unknown interpretation `{spec}`")),
+    }
+}
+
+/// Returns the total score of following the guide read with `interpretation`.
+fn play_guide(input: &str, rules: &Rules, interpretation: &dyn GuideInterpretation) -> usize {
+    let mut score = 0;
+    let mut previous = None;
+    for line in input.lines() {
+        let (first, second) = line.split_once(' ').unwrap();
+        let opponent = rules.match_hand(first).unwrap();
+        let round = Round {
+            opponent,
+            code: second.trim(),
+            previous,
+        };
+        let my_hand = interpretation
+            .play(rules, &round)
+            .unwrap_or_else(|| panic!("{} cannot read `{line}`", interpretation.name(rules)));
+        score += rules.score(my_hand, my_hand.outcome(opponent));
+        previous = Some(opponent);
+    }
+    score
+}
+
+/// Returns every mapping of the codes in the second column to a shape or an outcome with which
+/// following the guide scores `target`, mixed mappings and codes sharing a meaning included.
+fn infer_mappings(input: &str, rules: &Rules, target: usize) -> Vec<Mapping> {
+    // Only the number of times each (opponent, code) pair occurs matters for the score.
+    let mut rounds: HashMap<(Shape, &str), usize> = HashMap::new();
+    for line in input.lines() {
+        let (first, second) = line.split_once(' ').unwrap();
+        let opponent = rules.match_hand(first).unwrap();
+        *rounds.entry((opponent.shape, second.trim())).or_default() += 1;
+    }
+    let mut codes: Vec<&str> = rounds.keys().map(|(_, code)| *code).collect();
+    codes.sort();
+    codes.dedup();
+
+    let responses: Vec<Response> = rules
+        .hands
+        .iter()
+        .map(|hand| Response::Shape(hand.shape))
+        .chain([GameEnd::Lose, GameEnd::Draw, GameEnd::Win].map(Response::End))
+        .collect();
+    let mut consistent = Vec::new();
+    // Odometer over the response of every code.
+    let mut choice = vec![0; codes.len()];
+    loop {
+        let mapping = Mapping {
+            responses: codes
+                .iter()
+                .zip(&choice)
+                .map(|(code, &idx)| (code.to_string(), responses[idx]))
+                .collect(),
+        };
+        let score: usize = rounds
+            .iter()
+            .map(|(&(opponent, code), count)| {
+                let opponent = rules.hand(opponent);
+                let round = Round {
+                    opponent,
+                    code,
+                    previous: None,
+                };
+                let my_hand = mapping.play(rules, &round).unwrap();
+                count * rules.score(my_hand, my_hand.outcome(opponent))
+            })
+            .sum();
+        if score == target {
+            consistent.push(mapping);
+        }
+
+        let Some(idx) = choice.iter().rposition(|&idx| idx + 1 < responses.len()) else {
+            break;
+        };
+        choice[idx] += 1;
+        choice[idx + 1..].fill(0);
+    }
+    consistent
+}
+
+/// The probability of the opponent playing each shape, independently in every round.
+struct OpponentModel {
+    probabilities: Vec<f64>,
+}
+
+impl OpponentModel {
+    fn uniform(rules: &Rules) -> Self {
+        let count = rules.hands.len();
+        Self {
+            probabilities: vec![1.0 / count as f64; count],
+        }
+    }
+
+    /// Estimates the probabilities from the frequencies in the first column of the guide.
+    fn from_guide(input: &str, rules: &Rules) -> Self {
+        let mut counts = vec![0usize; rules.hands.len()];
+        for line in input.lines() {
+            let (first, _) = line.split_once(' ').unwrap();
+            counts[rules.match_hand(first).unwrap().shape.0] += 1;
+        }
+        let total = counts.iter().sum::<usize>().max(1) as f64;
+        Self {
+            probabilities: counts.iter().map(|&count| count as f64 / total).collect(),
+        }
+    }
+
+    fn parse(spec: &str, input: &str, rules: &Rules) -> Result<Self, String> {
+        match spec {
+            "uniform" => Ok(Self::uniform(rules)),
+            "guide" => Ok(Self::from_guide(input, rules)),
+            _ => Err(format!("unknown opponent model `{spec}`")),
+        }
+    }
+
+    /// The shapes the opponent may play with their probability.
+    fn shapes(&self) -> impl Iterator<Item = (Shape, f64)> + '_ {
+        self.probabilities
+            .iter()
+            .enumerate()
+            .filter(|(_, &probability)| probability > 0.0)
+            .map(|(idx, &probability)| (Shape(idx), probability))
+    }
+}
+
+/// Returns the expected score of following the guide read with `interpretation` when the first
+/// column is ignored and the opponent plays according to `model` instead.
+fn expected_score(
+    input: &str,
+    rules: &Rules,
+    interpretation: &dyn GuideInterpretation,
+    model: &OpponentModel,
+) -> f64 {
+    let mut expected = 0.0;
+    for (idx, line) in input.lines().enumerate() {
+        let (_, second) = line.split_once(' ').unwrap();
+        // The hand played in the round before is random as well.
+        let previous: Vec<(Option<Shape>, f64)> = if idx == 0 {
+            vec![(None, 1.0)]
+        } else {
+            model.shapes().map(|(shape, p)| (Some(shape), p)).collect()
+        };
+        for (shape, p) in model.shapes() {
+            let opponent = rules.hand(shape);
+            for &(previous, q) in &previous {
+                let round = Round {
+                    opponent,
+                    code: second.trim(),
+                    previous: previous.map(|shape| rules.hand(shape)),
+                };
+                let my_hand = interpretation.play(rules, &round).unwrap_or_else(|| {
+                    panic!("{} cannot read `{line}`", interpretation.name(rules))
+                });
+                expected += p * q * rules.score(my_hand, my_hand.outcome(opponent)) as f64;
+            }
+        }
+    }
+    expected
+}
+
+/// The scores of playing the shape of the row against the shape of the column.
+fn payoff_matrix(rules: &Rules) -> Vec<Vec<f64>> {
+    rules
+        .hands
+        .iter()
+        .map(|hand| {
+            rules
+                .hands
+                .iter()
+                .map(|opponent| rules.score(hand, hand.outcome(opponent)) as f64)
+                .collect()
+        })
+        .collect()
+}
+
+/// Returns the mixed strategy maximizing the expected score against the opponent that plays
+/// best against it, with the score per round it guarantees.
+///
+/// Solves the dual `max sum(v)` subject to `payoff * v <= 1`, `v >= 0` of the game's linear
+/// program with the simplex method; the strategy is read from the final tableau.
+fn optimal_strategy(payoff: &[Vec<f64>]) -> (Vec<f64>, f64) {
+    const EPSILON: f64 = 1e-9;
+    let rows = payoff.len();
+    let columns = payoff[0].len();
+    // Make all payoffs positive so the value of the game is positive as well.
+    let lowest = payoff
+        .iter()
+        .flatten()
+        .copied()
+        .fold(f64::INFINITY, f64::min);
+    let shift = if lowest > 0.0 { 0.0 } else { 1.0 - lowest };
+
+    // Each row holds the coefficients of v, the slack variables and the right hand side.
+    let width = columns + rows + 1;
+    let mut tableau: Vec<Vec<f64>> = (0..rows)
+        .map(|row| {
+            let mut line = vec![0.0; width];
+            for column in 0..columns {
+                line[column] = payoff[row][column] + shift;
+            }
+            line[columns + row] = 1.0;
+            line[width - 1] = 1.0;
+            line
+        })
+        .collect();
+    let mut objective = vec![0.0; width];
+    objective[..columns].fill(-1.0);
+
+    // Bland's rule: the first improving column, the first row with the smallest ratio.
+    while let Some(pivot_column) = (0..width - 1).find(|&column| objective[column] < -EPSILON) {
+        let pivot_row = (0..rows)
+            .filter(|&row| tableau[row][pivot_column] > EPSILON)
+            .min_by(|&a, &b| {
+                let ratio = |row: usize| tableau[row][width - 1] / tableau[row][pivot_column];
+                ratio(a).total_cmp(&ratio(b))
+            })
+            .expect("the game's linear program is bounded");
+        let pivot = tableau[pivot_row][pivot_column];
+        tableau[pivot_row]
+            .iter_mut()
+            .for_each(|value| *value /= pivot);
+        let pivot_line = tableau[pivot_row].clone();
+        let eliminate = |line: &mut Vec<f64>| {
+            let factor = line[pivot_column];
+            line.iter_mut()
+                .zip(&pivot_line)
+                .for_each(|(value, pivot)| *value -= factor * pivot);
+        };
+        for (row, line) in tableau.iter_mut().enumerate() {
+            if row != pivot_row {
+                eliminate(line);
+            }
+        }
+        eliminate(&mut objective);
+    }
+
+    let total = objective[width - 1];
+    let strategy = objective[columns..columns + rows]
+        .iter()
+        .map(|dual| dual / total)
+        .collect();
+    (strategy, 1.0 / total - shift)
+}
+
+/// A tournament player's way of choosing shapes.
+trait Strategy {
+    /// Returns the shape to play given the shapes played so far in the match, as (own, other).
+    fn play(&self, rules: &Rules, history: &[(Shape, Shape)]) -> Shape;
+}
+
+/// Plays the shapes of a strategy guide in order, starting over when it runs out.
+///
+/// Every line holds the code of a shape, the last one if there are more, so an AoC guide plays
+/// its second column.
+struct GuideStrategy {
+    shapes: Vec<Shape>,
+}
+
+impl GuideStrategy {
+    fn parse(text: &str, rules: &Rules) -> Result<Self, String> {
+        let shapes: Vec<Shape> = text
+            .lines()
+            .enumerate()
+            .filter_map(|(idx, line)| Some((idx, line.split_whitespace().last()?)))
+            .map(|(idx, code)| match rules.match_hand(code) {
+                Some(hand) => Ok(hand.shape),
+                None => Err(format!("line {}: unknown shape `{code}`", idx + 1)),
+            })
+            .collect::<Result<_, _>>()?;
+        if shapes.is_empty() {
+            return Err("empty strategy guide".to_owned());
+        }
+        Ok(Self { shapes })
+    }
+}
+
+impl Strategy for GuideStrategy {
+    fn play(&self, _rules: &Rules, history: &[(Shape, Shape)]) -> Shape {
+        self.shapes[history.len() % self.shapes.len()]
+    }
+}
+
+/// The strategy programs a player can pick instead of a guide.
+enum Program {
+    /// Always plays the same shape.
+    Always(Shape),
+    /// Plays the shapes in table order.
+    Cycle,
+    /// Plays the other player's previous shape, the first shape first.
+    Copy,
+    /// Plays what beats the other player's previous shape, the first shape first.
+    BeatLast,
+    /// Plays what beats the shape the other player played most so far, the first shape first.
+    Counter,
+}
+
+impl Strategy for Program {
+    fn play(&self, rules: &Rules, history: &[(Shape, Shape)]) -> Shape {
+        let beat = |shape| rules.hand_for(rules.hand(shape), GameEnd::Win).shape;
+        let previous = history.last().map(|&(_, other)| other);
+        match self {
+            Program::Always(shape) => *shape,
+            Program::Cycle => Shape(history.len() % rules.hands.len()),
+            Program::Copy => previous.unwrap_or(Shape(0)),
+            Program::BeatLast => previous.map(beat).unwrap_or(Shape(0)),
+            Program::Counter => {
+                let mut counts = vec![0usize; rules.hands.len()];
+                for &(_, other) in history {
+                    counts[other.0] += 1;
+                }
+                // The first of the most played shapes on a tie.
+                let most = (0..counts.len()).rev().max_by_key(|&idx| counts[idx]);
+                match most {
+                    Some(idx) if !history.is_empty() => beat(Shape(idx)),
+                    _ => Shape(0),
+                }
+            }
+        }
+    }
+}
+
+/// Parses a player's strategy: `guide:<path>`, `always:<shape>`, `cycle`, `copy`, `beat-last` or
+/// `counter`.
+fn parse_strategy(spec: &str, rules: &Rules) -> Result<Box<dyn Strategy>, String> {
+    let (kind, argument) = spec.split_once(':').unwrap_or((spec, ""));
+    match kind {
+        "guide" => {
+            let text = std::fs::read_to_string(argument)
+                .map_err(|e| format!("could not read {argument}: {e}"))?;
+            let guide =
+                GuideStrategy::parse(&text, rules).map_err(|e| format!("{argument}: {e}"))?;
+            Ok(Box::new(guide))
+        }
+        "always" => match rules.hands.iter().find(|hand| hand.name == argument) {
+            Some(hand) => Ok(Box::new(Program::Always(hand.shape))),
+            None => Err(format!("unknown shape `{argument}`")),
+        },
+        "cycle" => Ok(Box::new(Program::Cycle)),
+        "copy" => Ok(Box::new(Program::Copy)),
+        "beat-last" => Ok(Box::new(Program::BeatLast)),
+        "counter" => Ok(Box::new(Program::Counter)),
+        _ => Err(format!("unknown strategy `{spec}`")),
+    }
+}
+
+/// A named player in a tournament.
+struct Player {
+    name: String,
+    strategy: Box<dyn Strategy>,
+}
+
+/// A tournament, read from a TOML file such as:
+///
+/// ```toml
+/// rounds = 100
+///
+/// [players]
+/// alice = "guide:inputs/day02.txt"
+/// bob = "counter"
+/// carol = "always:rock"
+/// ```
+struct Tournament {
+    /// The number of rounds in every match.
+    rounds: usize,
+    players: Vec<Player>,
+}
+
+impl Tournament {
+    fn parse(text: &str, rules: &Rules) -> Result<Self, String> {
+        let document: Table = text.parse().map_err(|e: toml::de::Error| e.to_string())?;
+        let rounds = match document.get("rounds") {
+            Some(Value::Integer(rounds)) if *rounds > 0 => *rounds as usize,
+            Some(_) => return Err("`rounds` must be a positive integer".to_owned()),
+            None => 100,
+        };
+        let players = document
+            .get("players")
+            .and_then(Value::as_table)
+            .ok_or("missing [players] section")?
+            .iter()
+            .map(|(name, spec)| match spec {
+                Value::String(spec) => Ok(Player {
+                    name: name.clone(),
+                    strategy: parse_strategy(spec, rules).map_err(|e| format!("{name}: {e}"))?,
+                }),
+                _ => Err(format!("{name}: the strategy must be a string")),
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        if players.len() < 2 {
+            return Err("a tournament needs at least two players".to_owned());
+        }
+        Ok(Self { rounds, players })
+    }
+
+    /// Plays every pair of players once and returns the league table, best player first.
+    fn round_robin(&self, rules: &Rules) -> Vec<Standing> {
+        let mut standings: Vec<Standing> = self
+            .players
+            .iter()
+            .map(|player| Standing {
+                name: player.name.clone(),
+                ..Standing::default()
+            })
+            .collect();
+        for a in 0..self.players.len() {
+            for b in a + 1..self.players.len() {
+                let (score_a, score_b) = play_match(
+                    rules,
+                    self.players[a].strategy.as_ref(),
+                    self.players[b].strategy.as_ref(),
+                    self.rounds,
+                );
+                standings[a].record(score_a, score_b);
+                standings[b].record(score_b, score_a);
+            }
+        }
+        standings.sort_by(|a, b| {
+            (b.points(), b.score)
+                .cmp(&(a.points(), a.score))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        standings
+    }
+}
+
+/// Returns the total scores of two strategies playing `rounds` rounds against each other.
+fn play_match(rules: &Rules, a: &dyn Strategy, b: &dyn Strategy, rounds: usize) -> (usize, usize) {
+    let mut history_a = Vec::with_capacity(rounds);
+    let mut history_b = Vec::with_capacity(rounds);
+    let (mut score_a, mut score_b) = (0, 0);
+    for _ in 0..rounds {
+        let hand_a = rules.hand(a.play(rules, &history_a));
+        let hand_b = rules.hand(b.play(rules, &history_b));
+        score_a += rules.score(hand_a, hand_a.outcome(hand_b));
+        score_b += rules.score(hand_b, hand_b.outcome(hand_a));
+        history_a.push((hand_a.shape, hand_b.shape));
+        history_b.push((hand_b.shape, hand_a.shape));
+    }
+    (score_a, score_b)
+}
+
+/// A player's row in the league table. The match with the higher total score is won.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Standing {
+    name: String,
+    wins: usize,
+    draws: usize,
+    losses: usize,
+    /// The total score over all matches.
+    score: usize,
+}
+
+impl Standing {
+    fn record(&mut self, own: usize, other: usize) {
+        match own.cmp(&other) {
+            std::cmp::Ordering::Greater => self.wins += 1,
+            std::cmp::Ordering::Equal => self.draws += 1,
+            std::cmp::Ordering::Less => self.losses += 1,
+        }
+        self.score += own;
+    }
+
+    /// Three points for a win, one for a draw.
+    fn points(&self) -> usize {
+        3 * self.wins + self.draws
+    }
+}
+
+fn league_table(standings: &[Standing]) -> String {
+    let width = standings
+        .iter()
+        .map(|s| s.name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut table = format!(
+        "{:<width$} {:>4} {:>4} {:>4} {:>6} {:>8}\n",
+        "player", "W", "D", "L", "points", "score"
+    );
+    for s in standings {
+        table += &format!(
+            "{:<width$} {:>4} {:>4} {:>4} {:>6} {:>8}\n",
+            s.name,
+            s.wins,
+            s.draws,
+            s.losses,
+            s.points(),
+            s.score
+        );
+    }
+    table
+}
+
+fn league_json(standings: &[Standing]) -> String {
+    let rows: Vec<_> = standings
+        .iter()
+        .map(|s| {
+            json!({
+                "player": s.name,
+                "wins": s.wins,
+                "draws": s.draws,
+                "losses": s.losses,
+                "points": s.points(),
+                "score": s.score,
+            })
+        })
+        .collect();
+    serde_json::to_string_pretty(&rows).unwrap()
+}
+
+fn main() {
+    let day = aoc_2022::day_name(file!());
+    let config = Config::from_env();
+    let rules = match config.args().value("rules") {
+        Some(path) => {
+            let text = std::fs::read_to_string(path)
+                .unwrap_or_else(|e| panic!("could not read {path}: {e}"));
+            Rules::parse(&text).unwrap_or_else(|e| panic!("invalid rules {path}: {e}"))
+        }
+        None => Rules::default(),
     };
+    if let Some(pattern) = config.args().value("batch") {
+        batch::run(&config, day, pattern, |input| solve(input, &rules));
+        return;
+    }
+
+    // Run a round-robin tournament between the players of a tournament file.
+    if let Some(path) = config.args().value("tournament") {
+        let text =
+            std::fs::read_to_string(path).unwrap_or_else(|e| panic!("could not read {path}: {e}"));
+        let tournament = Tournament::parse(&text, &rules)
+            .unwrap_or_else(|e| panic!("invalid tournament {path}: {e}"));
+        let standings = tournament.round_robin(&rules);
+        match config.output_format() {
+            OutputFormat::Table => print!("{}", league_table(&standings)),
+            OutputFormat::Json => println!("{}", league_json(&standings)),
+        }
+        return;
+    }
+
+    // Read input file
+    let input = config.read_input(day);
+
+    // Compare interpretations side by side, e.g. `--guide shape,outcome,rotate`. Specs with
+    // arguments are separated by `;`, e.g. `--guide "shape;map:X=rock,Y=draw,Z=win"`.
+    let specs = config.args().value("guide");
+    let interpretations: Vec<_> = match specs {
+        Some(specs) => {
+            let separator = if specs.contains(';') { ';' } else { ',' };
+            specs.split(separator).map(str::trim).collect()
+        }
+        None => vec!["shape", "outcome"],
+    }
+    .into_iter()
+    .map(|spec| {
+        parse_interpretation(spec, &rules).unwrap_or_else(|e| panic!("invalid --guide: {e}"))
+    })
+    .collect();
+
+    // Judge the guide against a random opponent, `--analyze --opponent uniform` or `guide`.
+    if config.args().flag("analyze") {
+        let spec: String = config.day_param(day, "opponent", "guide".to_owned());
+        let model = OpponentModel::parse(&spec, &input, &rules)
+            .unwrap_or_else(|e| panic!("invalid --opponent: {e}"));
+        let rounds = input.lines().count();
+        println!("Expected score against the {spec} opponent");
+        for interpretation in &interpretations {
+            let expected = expected_score(&input, &rules, interpretation.as_ref(), &model);
+            println!("{:<32} {expected:.1}", interpretation.name(&rules));
+        }
+        let (strategy, value) = optimal_strategy(&payoff_matrix(&rules));
+        println!("Optimal mixed strategy, {value:.3} per round");
+        for (hand, probability) in rules.hands.iter().zip(strategy) {
+            println!("{:<32} {probability:.3}", hand.name);
+        }
+        println!("{:<32} {:.1}", "guaranteed score", value * rounds as f64);
+        return;
+    }
+
+    if specs.is_some() {
+        for interpretation in &interpretations {
+            let score = play_guide(&input, &rules, interpretation.as_ref());
+            println!("{:<32} {score}", interpretation.name(&rules));
+        }
+        return;
+    }
+
+    // Find out what the second column means from a known total, e.g. `--target-score 15`.
+    if let Some(target) = config.args().parsed::<usize>("target-score") {
+        let mappings = infer_mappings(&input, &rules, target);
+        println!("{} mappings score {target}", mappings.len());
+        for mapping in mappings {
+            println!("{}", mapping.describe(&rules));
+        }
+        return;
+    }
+
+    let answers = solve(&input, &rules);
+
+    println!("Solution 1 = {}", answers[0]);
+    println!("Solution 2 = {}", answers[1]);
+}
+
+fn solve(input: &str, rules: &Rules) -> Vec<String> {
+    vec![
+        play_guide(input, rules, &AsShape).to_string(),
+        play_guide(input, rules, &AsOutcome).to_string(),
+    ]
+}
 
-    let match_hand = |s: &str| match s {
-        "A" | "X" => Some(&rock),
-        "B" | "Y" => Some(&paper),
-        "C" | "Z" => Some(&scissor),
-        _ => None,
+#[cfg(test)]
+mod test {
+    use super::{
+        expected_score, infer_mappings, league_json, optimal_strategy, parse_interpretation,
+        payoff_matrix, play_guide, solve, AsOutcome, AsShape, BeatPrevious, GameEnd, GuideStrategy,
+        OpponentModel, Program, Rules, RulesError, Shape, Standing, Strategy, Tournament,
+        ROCK_PAPER_SCISSORS,
     };
 
-    let lines = input.lines();
-    let mut my_score1 = 0;
-    let mut my_score2 = 0;
-    for line in lines {
-        let (first, second) = line.split_at(1);
-        let opponent_hand = match_hand(first).unwrap();
-
-        // Part one
-        let my_hand = match_hand(second.trim()).unwrap();
-        let outcome = my_hand.outcome(opponent_hand);
-
-        my_score1 += my_hand.shape as usize + outcome as usize;
-
-        // Part two
-        let outcome2 = match_end(second.trim()).unwrap();
-        let my_hand2_shape = match outcome2 {
-            GameEnd::Win => opponent_hand.looses_from,
-            GameEnd::Draw => opponent_hand.shape,
-            GameEnd::Lose => opponent_hand.wins_from,
+    const RPSLS: &str = include_str!("../../rules/rock_paper_scissors_lizard_spock.toml");
+
+    #[test]
+    fn test_default_rules() {
+        assert_eq!(solve("A Y\nB X\nC Z", &Rules::default()), vec!["15", "12"]);
+    }
+
+    #[test]
+    fn test_rock_paper_scissors_lizard_spock() {
+        let rules = Rules::parse(RPSLS).unwrap();
+        let lizard = rules.match_hand("D").unwrap();
+        let spock = rules.match_hand("Z").unwrap();
+        assert_eq!(lizard.outcome(spock), GameEnd::Win);
+        assert_eq!(spock.outcome(lizard), GameEnd::Lose);
+        // Part one: spock loses to lizard (5 + 0), scissors draws (3 + 3). Part two: rock is
+        // the first shape that beats lizard (1 + 6), paper the first to lose to scissors (2 + 0).
+        assert_eq!(solve("D Z\nC X", &rules), vec!["11", "9"]);
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let asymmetric = RPSLS.replace(
+            r#"rock = ["scissors", "lizard"]"#,
+            r#"rock = ["scissors", "lizard", "paper"]"#,
+        );
+        assert_eq!(
+            Rules::parse(&asymmetric).err(),
+            Some(RulesError::Asymmetric(
+                "rock".to_owned(),
+                "paper".to_owned()
+            ))
+        );
+        let incomplete = RPSLS.replace(r#"spock = ["rock", "scissors"]"#, r#"spock = ["rock"]"#);
+        assert_eq!(
+            Rules::parse(&incomplete).err(),
+            Some(RulesError::Incomplete(
+                "scissors".to_owned(),
+                "spock".to_owned()
+            ))
+        );
+        let reflexive = RPSLS.replace(r#"spock = ["rock", "scissors"]"#, r#"spock = ["spock"]"#);
+        assert_eq!(
+            Rules::parse(&reflexive).err(),
+            Some(RulesError::Reflexive("spock".to_owned()))
+        );
+        let unknown = RPSLS.replace(r#"spock = ["rock", "scissors"]"#, r#"spock = ["rok"]"#);
+        assert_eq!(
+            Rules::parse(&unknown).err(),
+            Some(RulesError::UnknownShape("rok".to_owned()))
+        );
+        let duplicate = RPSLS.replace(r#"spock = ["E", "Z"]"#, r#"spock = ["E", "A"]"#);
+        assert_eq!(
+            Rules::parse(&duplicate).err(),
+            Some(RulesError::DuplicateCode("A".to_owned()))
+        );
+        let transitive = ROCK_PAPER_SCISSORS
+            .replace(r#"rock = ["scissors"]"#, r#"rock = ["scissors", "paper"]"#)
+            .replace(r#"paper = ["rock"]"#, r#"paper = ["scissors"]"#)
+            .replace(r#"scissors = ["paper"]"#, r#"scissors = []"#);
+        assert_eq!(
+            Rules::parse(&transitive).err(),
+            Some(RulesError::NeverLoses("rock".to_owned()))
+        );
+        let single = ROCK_PAPER_SCISSORS.replace(
+            r#"shapes = ["rock", "paper", "scissors"]"#,
+            r#"shapes = ["rock"]"#,
+        );
+        assert_eq!(
+            Rules::parse(&single).err(),
+            Some(RulesError::TooFewShapes(1))
+        );
+    }
+
+    #[test]
+    fn test_interpretations() {
+        let rules = Rules::default();
+        let guide = "A Y\nB X\nC Z";
+        // Paper beats rock (8), then paper and scissors beat the previous rock and paper but draw
+        // with the current hands (5 and 6).
+        assert_eq!(play_guide(guide, &rules, &BeatPrevious), 8 + 5 + 6);
+        let rotate = parse_interpretation("rotate", &rules).unwrap();
+        // Paper beats rock (8), paper draws with paper (5), paper loses to scissors (2).
+        assert_eq!(play_guide(guide, &rules, rotate.as_ref()), 8 + 5 + 2);
+        let mixed = parse_interpretation("map:X=rock,Y=draw,Z=win", &rules).unwrap();
+        assert_eq!(mixed.name(&rules), "map:X=rock,Y=draw,Z=win");
+        // Rock draws (4), rock loses to paper (1), rock beats scissors (7).
+        assert_eq!(play_guide(guide, &rules, mixed.as_ref()), 4 + 1 + 7);
+        assert!(parse_interpretation("map:X=stone", &rules).is_err());
+        assert!(parse_interpretation("guess", &rules).is_err());
+    }
+
+    #[test]
+    fn test_infer_mappings() {
+        let rules = Rules::default();
+        let guide = "A Y\nB X\nC Z";
+        let describe = |target| -> Vec<String> {
+            infer_mappings(guide, &rules, target)
+                .iter()
+                .map(|mapping| mapping.describe(&rules))
+                .collect()
+        };
+        let part_one = describe(15);
+        assert!(part_one.contains(&"X=rock,Y=paper,Z=scissors".to_owned()));
+        assert!(part_one.contains(&"X=lose,Y=paper,Z=scissors".to_owned()));
+        assert!(describe(12).contains(&"X=lose,Y=draw,Z=win".to_owned()));
+        // Winning every round: each code is either `win` or the shape beating its opponent.
+        assert_eq!(describe(24).len(), 8);
+        assert!(describe(24).contains(&"X=scissors,Y=win,Z=rock".to_owned()));
+        assert!(describe(100).is_empty());
+    }
+
+    #[test]
+    fn test_expected_score() {
+        let rules = Rules::default();
+        let guide = "A Y\nB X\nC Z";
+        let uniform = OpponentModel::uniform(&rules);
+        // Each shape scores 3 on average against a uniform opponent.
+        let expected = expected_score(guide, &rules, &AsShape, &uniform);
+        assert!((expected - (2.0 + 1.0 + 3.0 + 3.0 * 3.0)).abs() < 1e-9);
+        let expected = expected_score(guide, &rules, &AsOutcome, &uniform);
+        assert!((expected - (2.0 + 0.0 + 2.0 + 3.0 + 2.0 + 6.0)).abs() < 1e-9);
+        // The guide has one of each shape in the first column.
+        let from_guide = OpponentModel::from_guide(guide, &rules);
+        assert_eq!(
+            expected_score(guide, &rules, &AsShape, &from_guide),
+            expected_score(guide, &rules, &AsShape, &uniform)
+        );
+        let rocks = OpponentModel::from_guide("A X\nA Y", &rules);
+        assert_eq!(rocks.probabilities, [1.0, 0.0, 0.0]);
+        assert_eq!(expected_score("A Y", &rules, &AsShape, &rocks), 8.0);
+    }
+
+    #[test]
+    fn test_optimal_strategy() {
+        // Playing every shape a third of the time scores (1 + 5 + 9) / 3 whatever the opponent does.
+        let (strategy, value) = optimal_strategy(&payoff_matrix(&Rules::default()));
+        assert!(strategy.iter().all(|p| (p - 1.0 / 3.0).abs() < 1e-9));
+        assert!((value - 5.0).abs() < 1e-9);
+
+        // No opponent shape holds the score of the optimal strategy below its value.
+        let payoff = payoff_matrix(&Rules::parse(RPSLS).unwrap());
+        let (strategy, value) = optimal_strategy(&payoff);
+        assert!((strategy.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        for opponent in 0..payoff.len() {
+            let score: f64 = (0..payoff.len())
+                .map(|me| strategy[me] * payoff[me][opponent])
+                .sum();
+            assert!(score >= value - 1e-9);
+        }
+
+        // Without a saddle point both rows are mixed: x + 3(1 - x) = 4x + 2(1 - x).
+        let (strategy, value) = optimal_strategy(&[vec![1.0, 4.0], vec![3.0, 2.0]]);
+        assert!((strategy[0] - 0.25).abs() < 1e-9);
+        assert!((value - 2.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_strategies() {
+        let rules = Rules::default();
+        let (rock, paper, scissors) = (Shape(0), Shape(1), Shape(2));
+        let history = [(rock, paper), (rock, paper), (paper, scissors)];
+        assert_eq!(Program::Cycle.play(&rules, &history), rock);
+        assert_eq!(Program::Copy.play(&rules, &history), scissors);
+        assert_eq!(Program::BeatLast.play(&rules, &history), rock);
+        assert_eq!(Program::Counter.play(&rules, &history), scissors);
+        assert_eq!(Program::Counter.play(&rules, &[]), rock);
+
+        let guide = GuideStrategy::parse("A Y\nB X", &rules).unwrap();
+        assert_eq!(guide.play(&rules, &history[..2]), paper);
+        assert_eq!(guide.play(&rules, &history), rock);
+        assert_eq!(
+            GuideStrategy::parse("A Y\nB Q", &rules).err().unwrap(),
+            "line 2: unknown shape `Q`"
+        );
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let rules = Rules::default();
+        let tournament = Tournament::parse(
+            "rounds = 10\n\
+             [players]\n\
+             rocky = \"always:rock\"\n\
+             papyrus = \"always:paper\"\n\
+             copycat = \"copy\"\n",
+            &rules,
+        )
+        .unwrap();
+        let standings = tournament.round_robin(&rules);
+        let row = |name: &str, wins, draws, losses, score| Standing {
+            name: name.to_owned(),
+            wins,
+            draws,
+            losses,
+            score,
         };
-        my_score2 += my_hand2_shape as usize + outcome2 as usize;
+        // Paper beats rock (8 against 1 per round). Copying starts with rock, so it draws every
+        // round against rock (4) and loses only the first round against paper (1 + 9 * 5 against
+        // 8 + 9 * 5). Copying ranks above rock on the same points by its higher score.
+        assert_eq!(
+            standings,
+            [
+                row("papyrus", 2, 0, 0, 80 + 53),
+                row("copycat", 0, 1, 1, 40 + 46),
+                row("rocky", 0, 1, 1, 10 + 40),
+            ]
+        );
+        assert_eq!(standings[0].points(), 6);
+        let json: serde_json::Value = serde_json::from_str(&league_json(&standings)).unwrap();
+        assert_eq!(
+            json[0],
+            serde_json::json!({
+                "player": "papyrus", "wins": 2, "draws": 0, "losses": 0, "points": 6, "score": 133
+            })
+        );
+        assert!(Tournament::parse("[players]\nsolo = \"cycle\"", &rules).is_err());
+        assert!(Tournament::parse("[players]\na = \"cycle\"\nb = \"dance\"", &rules).is_err());
     }
-    println!("Solution 1 = {}", my_score1);
-    println!("Solution 2 = {}", my_score2);
 }
@@ -1,89 +1,1037 @@
-use std::path::Path;
+use aoc_2022::{batch, config::Config};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+use std::ops::RangeInclusive;
+use toml::{Table, Value};
 
 fn main() {
+    let day = aoc_2022::day_name(file!());
+    let config = Config::from_env();
+    // Number of elves that share a badge, e.g. `--group-size 4`.
+    let group_size: usize = config.day_param(day, "group_size", 3);
+    assert!(group_size > 0, "the group size must be positive");
+    let table = match config.args().value("priorities") {
+        Some(path) => {
+            let text = std::fs::read_to_string(path)
+                .unwrap_or_else(|e| panic!("could not read {path}: {e}"));
+            PriorityTable::parse(&text).unwrap_or_else(|e| panic!("invalid priorities {path}: {e}"))
+        }
+        None => PriorityTable::default(),
+    };
+    if let Some(pattern) = config.args().value("batch") {
+        batch::run(&config, day, pattern, |input| {
+            solve(input, group_size, &table)
+        });
+        return;
+    }
+
     // Read input file
-    let day = Path::new(file!()).file_stem().unwrap().to_str().unwrap();
-    let input = std::fs::read_to_string(format!("inputs/{day}.txt")).unwrap();
+    let input = config.read_input(day);
 
-    // Find common char in both compartments
-    let result: usize = input
-        .lines()
-        .map(find_overlapping_item)
-        .map(char_to_priority)
-        .sum();
+    // Look for groups among all rucksacks instead of consecutive ones.
+    if config.args().flag("find-groups") {
+        match find_partition(&input, group_size, &table) {
+            Ok(Some(partition)) => {
+                let uniqueness = if partition.unique {
+                    "unique"
+                } else {
+                    "not unique"
+                };
+                println!("Partition found ({uniqueness})");
+                println!("Priority sum of its badges : {}", partition.priority_sum);
+            }
+            Ok(None) => println!("No partition into groups of {group_size} with one badge"),
+            Err(e) => panic!("{e}"),
+        }
+        return;
+    }
+
+    // Propose the fewest item moves that leave no type in both compartments.
+    if config.args().flag("plan") {
+        let plan = plan_rearrangement(&input, &table);
+        for line in &plan.lines {
+            let moves: Vec<_> = line.moves.iter().map(Move::to_string).collect();
+            println!("line {}: {}", line.line, moves.join(", "));
+        }
+        for error in &plan.errors {
+            println!("error: {error}");
+        }
+        println!("Total moves : {}", plan.total_moves());
+        println!("Total priority affected : {}", plan.priority_affected());
+        return;
+    }
+
+    // Print every shared item and everything that is wrong with the input.
+    if config.args().flag("diagnose") {
+        print!("{}", diagnose(&input, group_size, &table));
+        return;
+    }
+
+    let answers = solve(&input, group_size, &table);
+
+    println!("Solution part 1 : {}", answers[0]);
+    println!("Solution part 2 : {}", answers[1]);
+}
+
+fn solve(input: &str, group_size: usize, table: &PriorityTable) -> Vec<String> {
+    let diagnostics = diagnose(input, group_size, table);
+    let (part1, part2) = diagnostics
+        .priority_sums()
+        .unwrap_or_else(|errors| panic!("{}", errors[0]));
+
+    vec![part1.to_string(), part2.to_string()]
+}
+
+/// The priority of every item type.
+///
+/// Tables are read from TOML files, where a key `a-z` is a range of items numbered from its
+/// priority on. The default table is:
+///
+/// ```toml
+/// [priorities]
+/// a-z = 1
+/// A-Z = 27
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PriorityTable {
+    /// The items ordered by priority, the position of an item is its bit in an [`ItemSet`].
+    items: Vec<(char, usize)>,
+    bits: HashMap<char, usize>,
+}
+
+impl Default for PriorityTable {
+    fn default() -> Self {
+        PriorityTable::builder()
+            .range('a'..='z', 1)
+            .range('A'..='Z', 27)
+            .build()
+            .unwrap()
+    }
+}
+
+impl PriorityTable {
+    fn builder() -> PriorityTableBuilder {
+        PriorityTableBuilder::default()
+    }
+
+    fn parse(text: &str) -> Result<Self, PriorityError> {
+        let document: Table = text.parse().map_err(PriorityError::Toml)?;
+        let section = document
+            .get("priorities")
+            .and_then(Value::as_table)
+            .ok_or(PriorityError::MissingSection)?;
+        let mut builder = PriorityTable::builder();
+        for (key, value) in section {
+            let priority = match value {
+                Value::Integer(priority) if *priority > 0 => *priority as usize,
+                _ => return Err(PriorityError::InvalidPriority(key.clone())),
+            };
+            let chars: Vec<char> = key.chars().collect();
+            builder = match chars[..] {
+                [item] => builder.item(item, priority),
+                [first, '-', last] if first <= last => builder.range(first..=last, priority),
+                _ => return Err(PriorityError::InvalidItem(key.clone())),
+            };
+        }
+        builder.build()
+    }
+
+    fn priority(&self, item: char) -> Option<usize> {
+        self.bits.get(&item).map(|&bit| self.items[bit].1)
+    }
+
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+}
+
+#[derive(Debug, Default)]
+struct PriorityTableBuilder {
+    items: Vec<(char, usize)>,
+}
+
+impl PriorityTableBuilder {
+    fn item(mut self, item: char, priority: usize) -> Self {
+        self.items.push((item, priority));
+        self
+    }
+
+    /// Adds the items of a range with increasing priorities starting at `first`.
+    fn range(mut self, items: RangeInclusive<char>, first: usize) -> Self {
+        self.items
+            .extend(items.enumerate().map(|(idx, item)| (item, first + idx)));
+        self
+    }
+
+    fn build(mut self) -> Result<PriorityTable, PriorityError> {
+        self.items.sort_by_key(|&(_, priority)| priority);
+        let mut bits = HashMap::with_capacity(self.items.len());
+        for (bit, &(item, _)) in self.items.iter().enumerate() {
+            if bits.insert(item, bit).is_some() {
+                return Err(PriorityError::Duplicate(item));
+            }
+        }
+        Ok(PriorityTable {
+            items: self.items,
+            bits,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PriorityError {
+    Toml(toml::de::Error),
+    MissingSection,
+    InvalidItem(String),
+    InvalidPriority(String),
+    Duplicate(char),
+}
+
+impl Display for PriorityError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PriorityError::Toml(e) => write!(f, "{e}"),
+            PriorityError::MissingSection => write!(f, "missing [priorities] section"),
+            PriorityError::InvalidItem(key) => {
+                write!(f, "`{key}` is neither an item nor a range like `a-z`")
+            }
+            PriorityError::InvalidPriority(key) => {
+                write!(f, "the priority of `{key}` must be a positive integer")
+            }
+            PriorityError::Duplicate(item) => write!(f, "`{item}` has several priorities"),
+        }
+    }
+}
+
+/// A set of item types, bit `n` is the `n`th item of a [`PriorityTable`]. The default table fits
+/// in a single 52-bit word.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ItemSet(Vec<u64>);
+
+impl ItemSet {
+    fn empty(table: &PriorityTable) -> Self {
+        ItemSet(vec![0; table.len().div_ceil(64)])
+    }
+
+    fn all(table: &PriorityTable) -> Self {
+        let mut set = ItemSet(vec![u64::MAX; table.len().div_ceil(64)]);
+        if let (Some(last), bits @ 1..) = (set.0.last_mut(), table.len() % 64) {
+            *last = (1 << bits) - 1;
+        }
+        set
+    }
+
+    /// Returns the set of items, or the first character that is not an item.
+    fn from_items(items: &str, table: &PriorityTable) -> Result<Self, char> {
+        let mut set = ItemSet::empty(table);
+        for c in items.chars() {
+            let bit = *table.bits.get(&c).ok_or(c)?;
+            set.0[bit / 64] |= 1 << (bit % 64);
+        }
+        Ok(set)
+    }
+
+    fn intersection(mut self, other: &ItemSet) -> Self {
+        for (word, other) in self.0.iter_mut().zip(&other.0) {
+            *word &= other;
+        }
+        self
+    }
+
+    /// Intersects any number of sets, no sets at all share every item.
+    fn intersect_all(table: &PriorityTable, sets: impl IntoIterator<Item = ItemSet>) -> Self {
+        sets.into_iter()
+            .fold(ItemSet::all(table), |all, set| all.intersection(&set))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.iter().all(|&word| word == 0)
+    }
+
+    fn len(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// The bits of the items in the set, so by priority.
+    fn bits(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.iter().enumerate().flat_map(|(idx, &word)| {
+            (0..64)
+                .filter(move |bit| word & 1 << bit != 0)
+                .map(move |bit| idx * 64 + bit)
+        })
+    }
+}
+
+/// An item found in several compartments or rucksacks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SharedItem {
+    item: char,
+    priority: usize,
+    /// How often the item occurs in each of them.
+    counts: Vec<usize>,
+}
+
+impl Display for SharedItem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let counts: Vec<_> = self.counts.iter().map(usize::to_string).collect();
+        write!(f, "{} ({})", self.item, counts.join("/"))
+    }
+}
+
+/// Returns every item that occurs in all `parts`, or the first character that is not an item.
+fn shared_items(parts: &[&str], table: &PriorityTable) -> Result<Vec<SharedItem>, char> {
+    let sets = parts
+        .iter()
+        .map(|part| ItemSet::from_items(part, table))
+        .collect::<Result<Vec<_>, _>>()?;
+    let shared = ItemSet::intersect_all(table, sets);
+    Ok(shared
+        .bits()
+        .map(|bit| table.items[bit])
+        .map(|(item, priority)| SharedItem {
+            item,
+            priority,
+            counts: parts
+                .iter()
+                .map(|part| part.chars().filter(|&c| c == item).count())
+                .collect(),
+        })
+        .collect())
+}
+
+/// Something that keeps the priorities from being summed, lines are numbered from one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RucksackError {
+    InvalidItem { line: usize, item: char },
+    OddLength { line: usize, length: usize },
+    NoSharedItem { line: usize },
+    NoBadge { first_line: usize, last_line: usize },
+    Unsplittable { line: usize },
+}
+
+impl Display for RucksackError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RucksackError::InvalidItem { line, item } => {
+                write!(f, "line {line}: `{item}` is not an item")
+            }
+            RucksackError::OddLength { line, length } => {
+                write!(
+                    f,
+                    "line {line}: {length} items do not split into two compartments"
+                )
+            }
+            RucksackError::NoSharedItem { line } => {
+                write!(f, "line {line}: the compartments share no item")
+            }
+            RucksackError::NoBadge {
+                first_line,
+                last_line,
+            } => write!(f, "lines {first_line}-{last_line}: the group has no badge"),
+            RucksackError::Unsplittable { line } => write!(
+                f,
+                "line {line}: the item types do not split into two compartments of equal size"
+            ),
+        }
+    }
+}
+
+/// The items shared by the compartments of a rucksack.
+#[derive(Debug, PartialEq, Eq)]
+struct RucksackReport {
+    line: usize,
+    shared: Vec<SharedItem>,
+}
+
+/// The items shared by the rucksacks of a group.
+#[derive(Debug, PartialEq, Eq)]
+struct GroupReport {
+    first_line: usize,
+    last_line: usize,
+    badges: Vec<SharedItem>,
+}
+
+#[derive(Debug, Default)]
+struct Diagnostics {
+    rucksacks: Vec<RucksackReport>,
+    groups: Vec<GroupReport>,
+    errors: Vec<RucksackError>,
+}
+
+impl Diagnostics {
+    /// The priority sums of the shared items and of the badges, if nothing is wrong with the
+    /// input. Where several items are shared, the one with the lowest priority counts.
+    fn priority_sums(&self) -> Result<(usize, usize), &[RucksackError]> {
+        if !self.errors.is_empty() {
+            return Err(&self.errors);
+        }
+        let sum = |shared: &[SharedItem]| shared[0].priority;
+        Ok((
+            self.rucksacks.iter().map(|r| sum(&r.shared)).sum(),
+            self.groups.iter().map(|g| sum(&g.badges)).sum(),
+        ))
+    }
+}
+
+impl Display for Diagnostics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let list = |shared: &[SharedItem]| {
+            let items: Vec<_> = shared.iter().map(SharedItem::to_string).collect();
+            items.join(", ")
+        };
+        for rucksack in &self.rucksacks {
+            writeln!(f, "line {}: {}", rucksack.line, list(&rucksack.shared))?;
+        }
+        for group in &self.groups {
+            writeln!(
+                f,
+                "lines {}-{}: {}",
+                group.first_line,
+                group.last_line,
+                list(&group.badges)
+            )?;
+        }
+        for error in &self.errors {
+            writeln!(f, "error: {error}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Finds the shared items of every rucksack and the badges of every group of `group_size`
+/// rucksacks, the last group may be smaller.
+fn diagnose(input: &str, group_size: usize, table: &PriorityTable) -> Diagnostics {
+    let mut diagnostics = Diagnostics::default();
+    let check_group = |diagnostics: &mut Diagnostics, group: &[&str], last_line: usize| {
+        let first_line = last_line + 1 - group.len();
+        // Invalid items are already reported for their line.
+        let Ok(badges) = shared_items(group, table) else {
+            return;
+        };
+        if badges.is_empty() {
+            diagnostics.errors.push(RucksackError::NoBadge {
+                first_line,
+                last_line,
+            });
+        }
+        diagnostics.groups.push(GroupReport {
+            first_line,
+            last_line,
+            badges,
+        });
+    };
+
+    // Only one group is kept in memory at a time.
+    let mut group = Vec::with_capacity(group_size);
+    let mut line = 0;
+    for rucksack in input.lines() {
+        line += 1;
+        group.push(rucksack);
+        if group.len() == group_size {
+            check_group(&mut diagnostics, &group, line);
+            group.clear();
+        }
 
-    println!("Solution part 1 : {}", result);
+        if let Some(item) = rucksack.chars().find(|&c| table.priority(c).is_none()) {
+            diagnostics
+                .errors
+                .push(RucksackError::InvalidItem { line, item });
+            continue;
+        }
+        let length = rucksack.chars().count();
+        if !length.is_multiple_of(2) {
+            diagnostics
+                .errors
+                .push(RucksackError::OddLength { line, length });
+            continue;
+        }
+        let middle = rucksack
+            .char_indices()
+            .nth(length / 2)
+            .map_or(rucksack.len(), |(idx, _)| idx);
+        let (left, right) = rucksack.split_at(middle);
+        let shared = shared_items(&[left, right], table).unwrap();
+        if shared.is_empty() {
+            diagnostics
+                .errors
+                .push(RucksackError::NoSharedItem { line });
+        }
+        diagnostics.rucksacks.push(RucksackReport { line, shared });
+    }
+    if !group.is_empty() {
+        check_group(&mut diagnostics, &group, line);
+    }
+    diagnostics
+}
 
-    let result: usize = input
+/// A partition of all rucksacks into groups that each share exactly one badge.
+#[derive(Debug, PartialEq, Eq)]
+struct Partition {
+    /// The line numbers of the rucksacks in every group.
+    groups: Vec<Vec<usize>>,
+    priority_sum: usize,
+    /// Whether no other partition exists.
+    unique: bool,
+}
+
+/// Searches for a partition of the rucksacks into groups of `group_size`, in any order, where
+/// every group shares exactly one item. Returns `None` if there is none.
+fn find_partition(
+    input: &str,
+    group_size: usize,
+    table: &PriorityTable,
+) -> Result<Option<Partition>, RucksackError> {
+    let sets = input
         .lines()
-        .collect::<Vec<_>>()
-        .chunks(3)
-        .map(find_overlapping_item_in_group)
-        .map(char_to_priority)
+        .enumerate()
+        .map(|(idx, rucksack)| {
+            ItemSet::from_items(rucksack, table).map_err(|item| RucksackError::InvalidItem {
+                line: idx + 1,
+                item,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if sets.len() % group_size != 0 {
+        return Ok(None);
+    }
+
+    let mut search = PartitionSearch::new(&sets, group_size);
+    search.search();
+    let Some(mut chosen) = search.first.take() else {
+        return Ok(None);
+    };
+    chosen.sort_by_key(|&id| search.group(id)[0]);
+    let priority_sum = chosen
+        .iter()
+        .map(|&id| table.items[search.badges[id]].1)
         .sum();
+    Ok(Some(Partition {
+        groups: chosen
+            .iter()
+            .map(|&id| search.group(id).iter().map(|idx| idx + 1).collect())
+            .collect(),
+        priority_sum,
+        unique: search.solutions == 1,
+    }))
+}
 
-    println!("Solution part 2 : {}", result);
+/// Exact cover of the rucksacks by valid groups, stops at the second partition found.
+///
+/// All groups sharing exactly one item are listed first, pruning candidates that share nothing
+/// with the group so far. The backtracking then always covers the rucksack with the fewest
+/// groups left, and drops the groups of every rucksack it covers.
+struct PartitionSearch {
+    group_size: usize,
+    /// The rucksacks of every group, `group_size` per group.
+    members: Vec<usize>,
+    /// The badge of every group, as a bit.
+    badges: Vec<usize>,
+    /// The groups every rucksack is in.
+    groups_of: Vec<Vec<usize>>,
+    /// The number of rucksacks of a group that are already covered, 0 if it is still possible.
+    covered_members: Vec<usize>,
+    /// The number of possible groups every rucksack is in.
+    options: Vec<usize>,
+    covered: Vec<bool>,
+    chosen: Vec<usize>,
+    first: Option<Vec<usize>>,
+    solutions: usize,
 }
 
-fn find_overlapping_item_in_group<'a>(group: &'a [&'a str]) -> char {
-    let first_sack = group[0];
-    for c in first_sack.chars() {
-        if group[1].contains(c) && group[2].contains(c) {
-            return c;
+impl PartitionSearch {
+    fn new(sets: &[ItemSet], group_size: usize) -> Self {
+        let mut search = Self {
+            group_size,
+            members: Vec::new(),
+            badges: Vec::new(),
+            groups_of: vec![Vec::new(); sets.len()],
+            covered_members: Vec::new(),
+            options: vec![0; sets.len()],
+            covered: vec![false; sets.len()],
+            chosen: Vec::new(),
+            first: None,
+            solutions: 0,
+        };
+        for start in 0..sets.len() {
+            search.list_groups(sets, &mut vec![start], sets[start].clone());
+        }
+        search.covered_members = vec![0; search.badges.len()];
+        search
+    }
+
+    /// Lists the groups that extend `group` with later rucksacks.
+    fn list_groups(&mut self, sets: &[ItemSet], group: &mut Vec<usize>, common: ItemSet) {
+        if group.len() == self.group_size {
+            if common.len() == 1 {
+                let id = self.badges.len();
+                self.badges.push(common.bits().next().unwrap());
+                for &member in group.iter() {
+                    self.members.push(member);
+                    self.groups_of[member].push(id);
+                    self.options[member] += 1;
+                }
+            }
+            return;
+        }
+        let last = *group.last().unwrap();
+        for next in last + 1..sets.len() {
+            let shared = common.clone().intersection(&sets[next]);
+            if shared.is_empty() {
+                continue;
+            }
+            group.push(next);
+            self.list_groups(sets, group, shared);
+            group.pop();
+        }
+    }
+
+    fn group(&self, id: usize) -> &[usize] {
+        &self.members[id * self.group_size..(id + 1) * self.group_size]
+    }
+
+    fn search(&mut self) {
+        let open = (0..self.covered.len()).filter(|&idx| !self.covered[idx]);
+        let Some(next) = open.min_by_key(|&idx| self.options[idx]) else {
+            self.solutions += 1;
+            self.first.get_or_insert_with(|| self.chosen.clone());
+            return;
+        };
+        let choices: Vec<usize> = self.groups_of[next]
+            .iter()
+            .copied()
+            .filter(|&id| self.covered_members[id] == 0)
+            .collect();
+        for id in choices {
+            let group = self.group(id).to_vec();
+            group.iter().for_each(|&member| self.cover(member));
+            self.chosen.push(id);
+            self.search();
+            self.chosen.pop();
+            group.iter().rev().for_each(|&member| self.uncover(member));
+            if self.solutions > 1 {
+                return;
+            }
+        }
+    }
+
+    fn cover(&mut self, rucksack: usize) {
+        self.covered[rucksack] = true;
+        for idx in 0..self.groups_of[rucksack].len() {
+            let id = self.groups_of[rucksack][idx];
+            if self.covered_members[id] == 0 {
+                for member in id * self.group_size..(id + 1) * self.group_size {
+                    self.options[self.members[member]] -= 1;
+                }
+            }
+            self.covered_members[id] += 1;
+        }
+    }
+
+    fn uncover(&mut self, rucksack: usize) {
+        for idx in (0..self.groups_of[rucksack].len()).rev() {
+            let id = self.groups_of[rucksack][idx];
+            self.covered_members[id] -= 1;
+            if self.covered_members[id] == 0 {
+                for member in id * self.group_size..(id + 1) * self.group_size {
+                    self.options[self.members[member]] += 1;
+                }
+            }
         }
+        self.covered[rucksack] = false;
     }
-    unreachable!()
 }
 
-fn find_overlapping_item(line: &str) -> char {
-    let (left, right) = line.split_at(line.len() / 2);
-    for c in left.chars() {
-        if right.contains(c) {
-            return c;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compartment {
+    Left,
+    Right,
+}
+
+/// Moves all items of a type that are in the other compartment to `to`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Move {
+    item: char,
+    priority: usize,
+    count: usize,
+    to: Compartment,
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (from, to) = match self.to {
+            Compartment::Left => ("right", "left"),
+            Compartment::Right => ("left", "right"),
+        };
+        write!(f, "move {} {} from {from} to {to}", self.count, self.item)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct LinePlan {
+    line: usize,
+    moves: Vec<Move>,
+}
+
+/// The fixes that leave no item type in both compartments of any rucksack.
+#[derive(Debug, Default)]
+struct Plan {
+    lines: Vec<LinePlan>,
+    errors: Vec<RucksackError>,
+}
+
+impl Plan {
+    fn total_moves(&self) -> usize {
+        self.moves().map(|m| m.count).sum()
+    }
+
+    /// The sum of the priorities of the moved item types, once per rucksack.
+    fn priority_affected(&self) -> usize {
+        self.moves().map(|m| m.priority).sum()
+    }
+
+    fn moves(&self) -> impl Iterator<Item = &Move> {
+        self.lines.iter().flat_map(|line| &line.moves)
+    }
+}
+
+/// Plans the fewest item moves between the compartments of every rucksack so that no item type
+/// is in both, while both compartments keep the same number of items.
+fn plan_rearrangement(input: &str, table: &PriorityTable) -> Plan {
+    let mut plan = Plan::default();
+    for (idx, rucksack) in input.lines().enumerate() {
+        let line = idx + 1;
+        match plan_rucksack(rucksack, line, table) {
+            Ok(moves) if moves.is_empty() => {}
+            Ok(moves) => plan.lines.push(LinePlan { line, moves }),
+            Err(e) => plan.errors.push(e),
         }
     }
-    unreachable!()
+    plan
 }
 
-fn char_to_priority(c: char) -> usize {
-    match c {
-        'a'..='z' => (c as usize) - ('a' as usize) + 1,
-        'A'..='Z' => (c as usize) - ('A' as usize) + 27,
-        _ => unreachable!(),
+fn plan_rucksack(
+    rucksack: &str,
+    line: usize,
+    table: &PriorityTable,
+) -> Result<Vec<Move>, RucksackError> {
+    let length = rucksack.chars().count();
+    if !length.is_multiple_of(2) {
+        return Err(RucksackError::OddLength { line, length });
+    }
+    // The number of items of every type in the left and right compartment, by priority.
+    let mut counts: BTreeMap<usize, (usize, usize)> = BTreeMap::new();
+    for (idx, item) in rucksack.chars().enumerate() {
+        let bit = *table
+            .bits
+            .get(&item)
+            .ok_or(RucksackError::InvalidItem { line, item })?;
+        let (left, right) = counts.entry(bit).or_default();
+        if idx < length / 2 {
+            *left += 1;
+        } else {
+            *right += 1;
+        }
+    }
+    let types: Vec<_> = counts.into_iter().collect();
+
+    // Every type ends up in one compartment. `cost[i][size]` is the fewest moves that put `size`
+    // items of the first `i` types in the left compartment.
+    let half = length / 2;
+    let mut cost = vec![vec![None; half + 1]; types.len() + 1];
+    cost[0][0] = Some(0);
+    for (i, &(_, (left, right))) in types.iter().enumerate() {
+        for size in 0..=half {
+            let Some(moves) = cost[i][size] else {
+                continue;
+            };
+            let mut relax = |size: usize, moves: usize| {
+                let best: &mut Option<usize> = &mut cost[i + 1][size];
+                if best.is_none_or(|best| moves < best) {
+                    *best = Some(moves);
+                }
+            };
+            relax(size, moves + left);
+            if size + left + right <= half {
+                relax(size + left + right, moves + right);
+            }
+        }
+    }
+    if cost[types.len()][half].is_none() {
+        return Err(RucksackError::Unsplittable { line });
+    }
+
+    let mut moves = Vec::new();
+    let mut size = half;
+    for (i, &(bit, (left, right))) in types.iter().enumerate().rev() {
+        let moves_here = cost[i + 1][size].unwrap();
+        let (item, priority) = table.items[bit];
+        if moves_here >= left && cost[i][size] == Some(moves_here - left) {
+            if left > 0 {
+                moves.push(Move {
+                    item,
+                    priority,
+                    count: left,
+                    to: Compartment::Right,
+                });
+            }
+        } else {
+            size -= left + right;
+            if right > 0 {
+                moves.push(Move {
+                    item,
+                    priority,
+                    count: right,
+                    to: Compartment::Left,
+                });
+            }
+        }
     }
+    moves.reverse();
+    Ok(moves)
 }
 
 #[cfg(test)]
 mod test {
-    use crate::{char_to_priority, find_overlapping_item_in_group};
+    use crate::{
+        diagnose, find_partition, plan_rearrangement, shared_items, solve, Compartment, ItemSet,
+        Move, Partition, PriorityError, PriorityTable, RucksackError, SharedItem,
+    };
+
+    const EXAMPLE: &str = "vJrwpWtwJgWrhcsFMMfFFhFp\n\
+                           jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL\n\
+                           PmmdzqPrVvPwwTWBwg\n\
+                           wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn\n\
+                           ttgJtRGJQctTZtZT\n\
+                           CrZsJsPPZsGzwwsLwLmpwMDw";
 
     #[test]
     fn test_char_to_priority() {
-        assert_eq!(char_to_priority('L'), 38);
-        assert_eq!(char_to_priority('a'), 1);
-        assert_eq!(char_to_priority('p'), 16);
-        assert_eq!(char_to_priority('P'), 42);
-        assert_eq!(char_to_priority('v'), 22);
-        assert_eq!(char_to_priority('s'), 19);
+        let table = PriorityTable::default();
+        assert_eq!(table.priority('L'), Some(38));
+        assert_eq!(table.priority('a'), Some(1));
+        assert_eq!(table.priority('p'), Some(16));
+        assert_eq!(table.priority('P'), Some(42));
+        assert_eq!(table.priority('v'), Some(22));
+        assert_eq!(table.priority('s'), Some(19));
+        assert_eq!(table.priority('é'), None);
     }
 
     #[test]
-    fn test_find_overlapping_item_in_group() {
+    fn test_shared_items_in_group() {
+        let table = PriorityTable::default();
         assert_eq!(
-            find_overlapping_item_in_group(&[
-                "vJrwpWtwJgWrhcsFMMfFFhFp",
-                "jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL",
-                "PmmdzqPrVvPwwTWBwg"
-            ]),
-            'r'
+            shared_items(
+                &[
+                    "vJrwpWtwJgWrhcsFMMfFFhFp",
+                    "jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL",
+                    "PmmdzqPrVvPwwTWBwg"
+                ],
+                &table
+            ),
+            Ok(vec![SharedItem {
+                item: 'r',
+                priority: 18,
+                counts: vec![2, 3, 1]
+            }])
         );
         assert_eq!(
-            find_overlapping_item_in_group(&[
-                "wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn",
-                "ttgJtRGJQctTZtZT",
-                "CrZsJsPPZsGzwwsLwLmpwMDw"
-            ]),
+            shared_items(
+                &[
+                    "wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn",
+                    "ttgJtRGJQctTZtZT",
+                    "CrZsJsPPZsGzwwsLwLmpwMDw"
+                ],
+                &table
+            )
+            .unwrap()[0]
+                .item,
             'Z'
         );
+        assert_eq!(shared_items(&["ab", "b-"], &table), Err('-'));
+    }
+
+    #[test]
+    fn test_item_set() {
+        let table = PriorityTable::default();
+        let set = |items| ItemSet::from_items(items, &table).unwrap();
+        assert_eq!(set("aZb").0, [1 | 2 | 1 << 51]);
+        assert_eq!(set("Zba").bits().collect::<Vec<_>>(), [0, 1, 51]);
+        let sets = ["abc", "bcd", "cde"].map(set);
+        assert_eq!(ItemSet::intersect_all(&table, sets), set("c"));
+        assert_eq!(set("ab").intersection(&set("AB")), ItemSet::empty(&table));
+        assert_eq!(ItemSet::intersect_all(&table, []).0, [(1 << 52) - 1]);
+        assert_eq!(ItemSet::from_items("a1", &table), Err('1'));
+    }
+
+    #[test]
+    fn test_priority_table() {
+        let table = PriorityTable::parse(
+            "[priorities]\n\
+             a-c = 10\n\
+             \"é\" = 1\n\
+             \"🍎\" = 100\n",
+        )
+        .unwrap();
+        assert_eq!(table.priority('b'), Some(11));
+        assert_eq!(table.priority('é'), Some(1));
+        assert_eq!(table.priority('d'), None);
+        // Shared items are listed by priority, the lowest counts.
+        assert_eq!(
+            solve("é🍎cé🍎a\nb🍎é🍎", 2, &table),
+            vec![(1 + 100).to_string(), 1.to_string()]
+        );
+        assert_eq!(
+            diagnose("aé🍎", 1, &table).errors[0].to_string(),
+            "line 1: 3 items do not split into two compartments"
+        );
+
+        // More items than fit in one word.
+        let wide = PriorityTable::builder()
+            .range('\u{100}'..='\u{1ff}', 1)
+            .build()
+            .unwrap();
+        let shared = shared_items(&["\u{100}\u{1f0}", "\u{1f0}\u{150}"], &wide).unwrap();
+        assert_eq!(shared[0].priority, 0xf1);
+        assert_eq!(ItemSet::all(&wide).bits().count(), 256);
+
+        assert_eq!(
+            PriorityTable::builder()
+                .item('a', 1)
+                .range('a'..='b', 2)
+                .build(),
+            Err(PriorityError::Duplicate('a'))
+        );
+        assert_eq!(
+            PriorityTable::parse("[priorities]\nab = 1"),
+            Err(PriorityError::InvalidItem("ab".to_owned()))
+        );
+        assert_eq!(
+            PriorityTable::parse("[priorities]\nx = 0"),
+            Err(PriorityError::InvalidPriority("x".to_owned()))
+        );
+        assert_eq!(
+            PriorityTable::parse("a = 1"),
+            Err(PriorityError::MissingSection)
+        );
+    }
+
+    #[test]
+    fn test_group_size() {
+        let table = PriorityTable::default();
+        assert_eq!(solve(EXAMPLE, 3, &table), vec!["157", "70"]);
+        assert_eq!(solve(EXAMPLE, 2, &table)[1], (6 + 17 + 33).to_string());
+        // The last group is smaller when the elves do not divide evenly.
+        let five = EXAMPLE.rsplit_once('\n').unwrap().0;
+        assert_eq!(solve(five, 2, &table)[1], (6 + 17 + 3).to_string());
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let table = PriorityTable::default();
+        let diagnostics = diagnose(EXAMPLE, 3, &table);
+        assert!(diagnostics.errors.is_empty());
+        assert_eq!(diagnostics.rucksacks.len(), 6);
+        assert_eq!(diagnostics.rucksacks[0].shared[0].to_string(), "p (1/1)");
+        assert_eq!(diagnostics.groups[1].last_line, 6);
+
+        // Several shared items are reported, but not errors.
+        assert_eq!(diagnose("abab", 1, &table).rucksacks[0].shared.len(), 2);
+        assert_eq!(diagnose("abab", 1, &table).groups[0].badges.len(), 2);
+
+        let errors = diagnose("abcb\nabcab\nabcd\nAa1a\nabab\ncdcd", 2, &table).errors;
+        assert_eq!(
+            errors,
+            [
+                RucksackError::OddLength { line: 2, length: 5 },
+                RucksackError::NoSharedItem { line: 3 },
+                RucksackError::InvalidItem { line: 4, item: '1' },
+                RucksackError::NoBadge {
+                    first_line: 5,
+                    last_line: 6
+                },
+            ]
+        );
+        assert_eq!(errors[3].to_string(), "lines 5-6: the group has no badge");
+    }
+
+    #[test]
+    fn test_find_partition() {
+        let table = PriorityTable::default();
+        // The example with its two groups interleaved.
+        let lines: Vec<_> = EXAMPLE.lines().collect();
+        let shuffled = [0, 3, 1, 4, 2, 5].map(|idx| lines[idx]).join("\n");
+        assert_eq!(
+            find_partition(&shuffled, 3, &table),
+            Ok(Some(Partition {
+                groups: vec![vec![1, 3, 5], vec![2, 4, 6]],
+                priority_sum: 70,
+                unique: false,
+            }))
+        );
+
+        let partition = find_partition("ab\nac\nbd\ncd", 2, &table)
+            .unwrap()
+            .unwrap();
+        assert_eq!(partition.groups, [vec![1, 2], vec![3, 4]]);
+        assert_eq!(partition.priority_sum, 1 + 4);
+        assert!(!partition.unique);
+
+        let unique = find_partition("ab\nde\nac\ndf", 2, &table)
+            .unwrap()
+            .unwrap();
+        assert_eq!(unique.groups, [vec![1, 3], vec![2, 4]]);
+        assert!(unique.unique);
+
+        assert_eq!(find_partition("ab\ncd\nab", 3, &table), Ok(None));
+        assert_eq!(find_partition("ab\ncd", 2, &table), Ok(None));
+        assert_eq!(find_partition("ab\nab\nab", 2, &table), Ok(None));
+        assert_eq!(
+            find_partition("ab\na!", 2, &table),
+            Err(RucksackError::InvalidItem { line: 2, item: '!' })
+        );
+    }
+
+    #[test]
+    fn test_plan_rearrangement() {
+        let table = PriorityTable::default();
+        let plan = plan_rearrangement(EXAMPLE, &table);
+        // Moving the single `p` to one side leaves the other side an item short, so the single
+        // `v` moves the other way.
+        assert_eq!(
+            plan.lines[0].moves,
+            [
+                Move {
+                    item: 'p',
+                    priority: 16,
+                    count: 1,
+                    to: Compartment::Left
+                },
+                Move {
+                    item: 'v',
+                    priority: 22,
+                    count: 1,
+                    to: Compartment::Right
+                }
+            ]
+        );
+        assert_eq!(
+            plan.lines[0].moves[0].to_string(),
+            "move 1 p from right to left"
+        );
+        assert_eq!(plan.lines.len(), 6);
+        assert_eq!(plan.total_moves(), 18);
+
+        // Keeping all `a`s left costs 2 moves, keeping `b` and `c` left would cost 4.
+        let plan = plan_rearrangement("aabcab\nabcabc\nabba\naaab\nabcd", &table);
+        assert_eq!(plan.lines.len(), 2);
+        assert_eq!(plan.total_moves(), 2 + 2);
+        assert_eq!(plan.priority_affected(), (1 + 2) * 2);
+        assert_eq!(
+            plan.errors,
+            [
+                RucksackError::Unsplittable { line: 2 },
+                RucksackError::Unsplittable { line: 4 }
+            ]
+        );
     }
 }
@@ -1,78 +1,756 @@
+use aoc_2022::interval::{parse_range, IntervalSet, ParseError};
+use aoc_2022::{batch, config::Config};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter, Write};
 use std::ops::RangeInclusive;
-use std::path::Path;
 
 fn main() {
+    let day = aoc_2022::day_name(file!());
+    let config = Config::from_env();
+    if let Some(pattern) = config.args().value("batch") {
+        batch::run(&config, day, pattern, solve);
+        return;
+    }
+
     // Read input file
-    let day = Path::new(file!()).file_stem().unwrap().to_str().unwrap();
-    let input = std::fs::read_to_string(format!("inputs/{day}.txt")).unwrap();
+    let input = config.read_input(day);
 
-    let result = input
-        .lines()
-        .map(parse_pairs)
-        .filter(|(a, b)| completely_contains(a, b))
+    // Remove the overlaps on every line by dropping assignments (`--reassign drop`) or by
+    // shrinking them (`--reassign shrink`). The new assignments are written in the input format,
+    // the totals to stderr. Lines that cannot be shrunk are reassigned by dropping, with a warning.
+    if let Some(mode) = config.args().value("reassign") {
+        let (mut changed_lines, mut dropped, mut removed_sections) = (0, 0, 0);
+        let lines = parse_lines(&input).unwrap_or_else(|e| panic!("{e}"));
+        for (idx, ranges) in lines.into_iter().enumerate() {
+            let adjusted = match mode {
+                "drop" => drop_overlaps(&ranges),
+                "shrink" => shrink_overlaps(&ranges).unwrap_or_else(|e| {
+                    eprintln!("line {}: {e}, dropping instead", idx + 1);
+                    drop_overlaps(&ranges)
+                }),
+                _ => panic!("invalid --reassign: expected `drop` or `shrink`, found `{mode}`"),
+            };
+            if adjusted != ranges {
+                changed_lines += 1;
+            }
+            dropped += ranges.len() - adjusted.len();
+            removed_sections += sections(&ranges) - sections(&adjusted);
+            println!("{}", format_ranges(&adjusted));
+        }
+        eprintln!(
+            "Changed lines : {changed_lines}, dropped assignments : {dropped}, \
+             removed sections : {removed_sections}"
+        );
+        return;
+    }
+
+    // Draw the assignments as the puzzle does, `--lines 3-5,9` selects the lines to draw and
+    // `--color` highlights the overlaps with ANSI colors.
+    if config.args().flag("timeline") {
+        let lines = config.args().parsed::<IntervalSet<usize>>("lines");
+        let width = config.day_param(day, "timeline_width", 100);
+        let color = config.args().flag("color");
+        let timeline = render_timeline(&input, lines.as_ref(), width, color);
+        print!("{}", timeline.unwrap_or_else(|e| panic!("{e}")));
+        return;
+    }
+
+    // Look for overlaps between the assignments of all lines, `--covering 12` lists the
+    // assignments covering section 12 and `--list-pairs` every overlapping pair.
+    if config.args().flag("overlaps") {
+        let assignments = parse_assignments(&input).unwrap_or_else(|e| panic!("{e}"));
+        let index = OverlapIndex::new(assignments);
+        if config.args().flag("list-pairs") {
+            index.for_each_overlapping_pair(|a, b| println!("{a} overlaps {b}"));
+        }
+        println!("Overlapping pairs : {}", index.count_overlapping_pairs());
+        if let Some((section, count)) = index.max_coverage() {
+            println!("Most covered section : {section} ({count} elves)");
+        }
+        if let Some(section) = config.args().parsed("covering") {
+            for assignment in index.covering(section) {
+                println!("Covers section {section} : {assignment}");
+            }
+        }
+        return;
+    }
+    let answers = solve(&input);
+
+    println!("Solution part 1 : {}", answers[0]);
+    println!("Solution part 2 : {}", answers[1]);
+}
+
+fn solve(input: &str) -> Vec<String> {
+    // Lines may assign sections to any number of elves, the pairs of the puzzle included.
+    let lines = parse_lines(input).unwrap_or_else(|e| panic!("{e}"));
+    let sets: Vec<Vec<IntervalSet<usize>>> = lines
+        .into_iter()
+        .map(|ranges| ranges.into_iter().map(IntervalSet::from).collect())
+        .collect();
+    let part1 = sets
+        .iter()
+        .filter(|sets| any_pair(sets, completely_contains))
         .count();
 
-    println!("Solution part 1 : {result}");
+    let part2 = sets.iter().filter(|sets| any_pair(sets, contains)).count();
 
-    let result = input
+    vec![part1.to_string(), part2.to_string()]
+}
+
+fn parse_ranges(line: &str) -> Result<Vec<RangeInclusive<usize>>, ParseError> {
+    line.split(',')
+        .map(|part| parse_range(part.trim()))
+        .collect()
+}
+
+/// Parses a line of the input, errors name the line by its number (from one).
+fn parse_line(number: usize, line: &str) -> Result<Vec<RangeInclusive<usize>>, ParseError> {
+    parse_ranges(line).map_err(|e| ParseError {
+        message: format!("line {number}: {e}"),
+    })
+}
+
+fn parse_lines(input: &str) -> Result<Vec<Vec<RangeInclusive<usize>>>, ParseError> {
+    input
         .lines()
-        .map(parse_pairs)
-        .filter(|(a, b)| contains(a, b))
-        .count();
+        .enumerate()
+        .map(|(idx, line)| parse_line(idx + 1, line))
+        .collect()
+}
+
+/// Returns whether `predicate` holds for any two of the elves' sections.
+fn any_pair(
+    sections: &[IntervalSet<usize>],
+    predicate: fn(&IntervalSet<usize>, &IntervalSet<usize>) -> bool,
+) -> bool {
+    sections
+        .iter()
+        .enumerate()
+        .any(|(idx, a)| sections[idx + 1..].iter().any(|b| predicate(a, b)))
+}
+
+fn completely_contains(a: &IntervalSet<usize>, b: &IntervalSet<usize>) -> bool {
+    a.is_subset(b) || b.is_subset(a)
+}
+
+fn contains(a: &IntervalSet<usize>, b: &IntervalSet<usize>) -> bool {
+    a.overlaps(b)
+}
+
+/// An elf's sections, `line` and `elf` number the assignments from one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Assignment {
+    line: usize,
+    elf: usize,
+    sections: RangeInclusive<usize>,
+}
+
+impl Display for Assignment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {} elf {} ({}-{})",
+            self.line,
+            self.elf,
+            self.sections.start(),
+            self.sections.end()
+        )
+    }
+}
+
+fn parse_assignments(input: &str) -> Result<Vec<Assignment>, ParseError> {
+    let lines = parse_lines(input)?;
+    Ok(lines
+        .into_iter()
+        .enumerate()
+        .flat_map(|(line, ranges)| {
+            ranges
+                .into_iter()
+                .enumerate()
+                .map(move |(elf, sections)| Assignment {
+                    line: line + 1,
+                    elf: elf + 1,
+                    sections,
+                })
+        })
+        .collect())
+}
+
+/// An index over the assignments of all lines, sorted by their first section.
+///
+/// Pairs and coverage are found with a sweep over the sections, assignments covering a section
+/// with a tree of the largest last section in every part of the sorted assignments.
+struct OverlapIndex {
+    assignments: Vec<Assignment>,
+    /// A segment tree over `assignments`, node 1 is the root and node `n` has children `2n`
+    /// and `2n + 1`.
+    max_end: Vec<usize>,
+}
+
+impl OverlapIndex {
+    fn new(mut assignments: Vec<Assignment>) -> Self {
+        assignments.sort_by_key(|a| (*a.sections.start(), *a.sections.end()));
+        let mut index = Self {
+            max_end: vec![0; 4 * assignments.len().max(1)],
+            assignments,
+        };
+        if !index.assignments.is_empty() {
+            index.build(1, 0, index.assignments.len());
+        }
+        index
+    }
+
+    fn build(&mut self, node: usize, from: usize, to: usize) -> usize {
+        self.max_end[node] = if to - from == 1 {
+            *self.assignments[from].sections.end()
+        } else {
+            let middle = (from + to) / 2;
+            self.build(2 * node, from, middle)
+                .max(self.build(2 * node + 1, middle, to))
+        };
+        self.max_end[node]
+    }
+
+    /// Calls `f` for every two assignments that share a section.
+    fn for_each_overlapping_pair(&self, mut f: impl FnMut(&Assignment, &Assignment)) {
+        // The assignments that started before and have not ended yet, by their end.
+        let mut active = BTreeSet::new();
+        for (idx, assignment) in self.assignments.iter().enumerate() {
+            let start = *assignment.sections.start();
+            while active.first().is_some_and(|&(end, _)| end < start) {
+                active.pop_first();
+            }
+            for &(_, other) in &active {
+                f(&self.assignments[other], assignment);
+            }
+            active.insert((*assignment.sections.end(), idx));
+        }
+    }
+
+    /// Counts the overlapping pairs without listing them.
+    fn count_overlapping_pairs(&self) -> usize {
+        let mut ends: Vec<usize> = self.assignments.iter().map(|a| *a.sections.end()).collect();
+        ends.sort_unstable();
+        // Every earlier assignment overlaps, except the ones that end before this one starts.
+        self.assignments
+            .iter()
+            .enumerate()
+            .map(|(idx, a)| idx - ends.partition_point(|&end| end < *a.sections.start()))
+            .sum()
+    }
+
+    /// Returns the first section covered by the most assignments, and how many cover it.
+    fn max_coverage(&self) -> Option<(usize, usize)> {
+        let mut events: Vec<(usize, bool)> = Vec::with_capacity(2 * self.assignments.len());
+        for a in &self.assignments {
+            events.push((*a.sections.start(), true));
+            // Sections beyond `usize::MAX` are never reached.
+            if let Some(after) = a.sections.end().checked_add(1) {
+                events.push((after, false));
+            }
+        }
+        // Ends before starts at the same section, the assignments are inclusive.
+        events.sort_unstable();
+        let mut covering = 0;
+        let mut best: Option<(usize, usize)> = None;
+        for (section, starts) in events {
+            if starts {
+                covering += 1;
+                if best.is_none_or(|(_, most)| covering > most) {
+                    best = Some((section, covering));
+                }
+            } else {
+                covering -= 1;
+            }
+        }
+        best
+    }
+
+    /// Returns the assignments that cover `section`, by their first section.
+    fn covering(&self, section: usize) -> Vec<&Assignment> {
+        let starting = self
+            .assignments
+            .partition_point(|a| *a.sections.start() <= section);
+        let mut found = Vec::new();
+        if starting > 0 {
+            self.collect_covering(1, 0, self.assignments.len(), starting, section, &mut found);
+        }
+        found
+    }
+
+    /// Collects the assignments in `from..to` before `starting` that end at or after `section`.
+    fn collect_covering<'a>(
+        &'a self,
+        node: usize,
+        from: usize,
+        to: usize,
+        starting: usize,
+        section: usize,
+        found: &mut Vec<&'a Assignment>,
+    ) {
+        if from >= starting || self.max_end[node] < section {
+            return;
+        }
+        if to - from == 1 {
+            found.push(&self.assignments[from]);
+            return;
+        }
+        let middle = (from + to) / 2;
+        self.collect_covering(2 * node, from, middle, starting, section, found);
+        self.collect_covering(2 * node + 1, middle, to, starting, section, found);
+    }
+}
+
+/// Keeps the most ranges of which no two overlap, dropping the others. The kept ranges stay in
+/// their order.
+fn drop_overlaps(ranges: &[RangeInclusive<usize>]) -> Vec<RangeInclusive<usize>> {
+    // Interval scheduling: the range that ends first never blocks more than another choice.
+    let mut by_end: Vec<usize> = (0..ranges.len()).collect();
+    by_end.sort_by_key(|&idx| (*ranges[idx].end(), *ranges[idx].start()));
+    let mut keep = vec![false; ranges.len()];
+    let mut last_end = None;
+    for idx in by_end {
+        if last_end.is_none_or(|end| *ranges[idx].start() > end) {
+            keep[idx] = true;
+            last_end = Some(*ranges[idx].end());
+        }
+    }
+    ranges
+        .iter()
+        .zip(keep)
+        .filter(|(_, keep)| *keep)
+        .map(|(range, _)| range.clone())
+        .collect()
+}
+
+/// The most ranges [`shrink_overlaps`] handles on one line.
+const MAX_SHRINK: usize = 12;
 
-    println!("Solution part 2 : {result}");
+/// Why the ranges of a line could not be shrunk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ShrinkError {
+    /// More ranges than [`MAX_SHRINK`], the search would take too long.
+    TooManyRanges(usize),
+    /// The ranges cannot all keep a section of their own.
+    Impossible,
 }
 
-fn parse_range(line: &str) -> RangeInclusive<usize> {
-    let (left, right) = line.split_once('-').unwrap();
-    left.parse().unwrap()..=right.parse().unwrap()
+impl Display for ShrinkError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShrinkError::TooManyRanges(count) => {
+                write!(f, "cannot shrink {count} ranges, at most {MAX_SHRINK}")
+            }
+            ShrinkError::Impossible => write!(f, "not every range can keep a section"),
+        }
+    }
+}
+
+/// Shrinks the ranges, removing the fewest sections in total, so that no two overlap. Every
+/// range keeps at least one section.
+fn shrink_overlaps(
+    ranges: &[RangeInclusive<usize>],
+) -> Result<Vec<RangeInclusive<usize>>, ShrinkError> {
+    if ranges.len() > MAX_SHRINK {
+        return Err(ShrinkError::TooManyRanges(ranges.len()));
+    }
+    // The shrunk ranges lie next to each other in some order. `best[(placed, frontier)]` is the
+    // most sections kept by placing the ranges in `placed` left of `frontier + 1`, and the step
+    // that got there. Only ending a range right before another starts, where it ended before
+    // or right after it starts can be worth it. Sections are widened to `i128` so that any
+    // `usize` and the frontier before it fit.
+    let bounds: Vec<(i128, i128)> = ranges
+        .iter()
+        .map(|r| (*r.start() as i128, *r.end() as i128))
+        .collect();
+    let mut frontiers: Vec<i128> = bounds.iter().flat_map(|&(s, e)| [s - 1, e]).collect();
+    frontiers.sort_unstable();
+    frontiers.dedup();
+
+    type Step = (usize, i128, usize, i128);
+    let mut best: BTreeMap<(usize, i128), (i128, Option<Step>)> = BTreeMap::new();
+    best.insert((0, i128::MIN), (0, None));
+    let mut masks: Vec<usize> = (0..1 << ranges.len()).collect();
+    masks.sort_by_key(|mask| mask.count_ones());
+    for placed in masks {
+        let states: Vec<_> = best
+            .range((placed, i128::MIN)..=(placed, i128::MAX))
+            .map(|(&(_, frontier), &(kept, _))| (frontier, kept))
+            .collect();
+        for (frontier, kept) in states {
+            for (idx, &(start, end)) in bounds.iter().enumerate() {
+                if placed & 1 << idx != 0 {
+                    continue;
+                }
+                let from = start.max(frontier.saturating_add(1));
+                // Keeping just the first section leaves the most room for the others.
+                let ends = frontiers.iter().copied().chain([from]);
+                for to in ends.filter(|&to| from <= to && to <= end) {
+                    let kept = kept + to - from + 1;
+                    let entry = best.entry((placed | 1 << idx, to)).or_insert((-1, None));
+                    if kept > entry.0 {
+                        *entry = (kept, Some((placed, frontier, idx, from)));
+                    }
+                }
+            }
+        }
+    }
+
+    let all = (1 << ranges.len()) - 1;
+    let (state, _) = best
+        .range((all, i128::MIN)..=(all, i128::MAX))
+        .max_by_key(|(&(_, frontier), &(kept, _))| (kept, -frontier))
+        .ok_or(ShrinkError::Impossible)?;
+    let mut state = *state;
+    let mut shrunk = ranges.to_vec();
+    while let Some((_, Some((placed, frontier, idx, from)))) = best.get(&state) {
+        shrunk[*idx] = *from as usize..=state.1 as usize;
+        state = (*placed, *frontier);
+    }
+    Ok(shrunk)
 }
 
-fn parse_pairs(line: &str) -> (RangeInclusive<usize>, RangeInclusive<usize>) {
-    let (left, right) = line.split_once(',').unwrap();
-    (parse_range(left), parse_range(right))
+/// The total number of sections in the ranges, a single range may hold every `usize`.
+fn sections(ranges: &[RangeInclusive<usize>]) -> u128 {
+    ranges
+        .iter()
+        .map(|r| (r.end() - r.start()) as u128 + 1)
+        .sum()
 }
 
-fn completely_contains(a: &RangeInclusive<usize>, b: &RangeInclusive<usize>) -> bool {
-    a.start() >= b.start() && a.end() <= b.end() || b.start() >= a.start() && b.end() <= a.end()
+/// Formats ranges as a line of the input, `a-b,c-d`.
+fn format_ranges(ranges: &[RangeInclusive<usize>]) -> String {
+    let ranges: Vec<_> = ranges
+        .iter()
+        .map(|r| format!("{}-{}", r.start(), r.end()))
+        .collect();
+    ranges.join(",")
 }
 
-fn contains(a: &RangeInclusive<usize>, b: &RangeInclusive<usize>) -> bool {
-    a.start() <= b.end() && b.start() <= a.end()
+/// The sections drawn by a timeline, each column stands for `scale` sections.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Timeline {
+    first: usize,
+    columns: usize,
+    scale: usize,
+}
+
+impl Timeline {
+    /// Spans the sections of all lines and a free section on both sides, scaled down to at most
+    /// `width` columns.
+    fn new(lines: &[Vec<RangeInclusive<usize>>], width: usize) -> Option<Self> {
+        let first = lines.iter().flatten().map(|r| *r.start()).min()?;
+        let last = lines.iter().flatten().map(|r| *r.end()).max()?;
+        let (first, last) = (first.saturating_sub(1), last.saturating_add(1));
+        let span = (last - first).saturating_add(1);
+        let scale = span.div_ceil(width.max(1));
+        Some(Self {
+            first,
+            columns: span.div_ceil(scale),
+            scale,
+        })
+    }
+
+    /// The sections drawn in a column.
+    fn sections(&self, column: usize) -> RangeInclusive<usize> {
+        let start = self.first + column * self.scale;
+        start..=start.saturating_add(self.scale - 1)
+    }
+}
+
+const OVERLAP_COLOR: &str = "\x1b[1;33m";
+const CONTAINED_COLOR: &str = "\x1b[36m";
+const RESET_COLOR: &str = "\x1b[0m";
+
+/// Draws the ranges of a line one per row, e.g. `.234.....  2-4`.
+///
+/// Sections are drawn by their last digit, or as `#` when a column stands for several sections.
+/// The sections shared by two ranges or more are marked with `^` in an extra row, or drawn in
+/// yellow with `color`, and the ranges fully contained by another one are labelled.
+fn render_line(ranges: &[RangeInclusive<usize>], timeline: &Timeline, color: bool) -> String {
+    let sets: Vec<_> = ranges.iter().cloned().map(IntervalSet::from).collect();
+    let mut shared = IntervalSet::new();
+    for (idx, a) in sets.iter().enumerate() {
+        for b in &sets[idx + 1..] {
+            shared = shared.union(&a.intersection(b));
+        }
+    }
+    let overlapping: Vec<bool> = (0..timeline.columns)
+        .map(|column| shared.overlaps_range(&timeline.sections(column)))
+        .collect();
+
+    let mut out = String::new();
+    for (idx, (range, set)) in ranges.iter().zip(&sets).enumerate() {
+        for (column, &overlap) in overlapping.iter().enumerate() {
+            let sections = timeline.sections(column);
+            if !set.overlaps_range(&sections) {
+                out.push('.');
+                continue;
+            }
+            let mark = match timeline.scale {
+                1 => char::from_digit((sections.start() % 10) as u32, 10).unwrap(),
+                _ => '#',
+            };
+            if color && overlap {
+                write!(out, "{OVERLAP_COLOR}{mark}{RESET_COLOR}").unwrap();
+            } else {
+                out.push(mark);
+            }
+        }
+        write!(out, "  {}-{}", range.start(), range.end()).unwrap();
+        let outer = ranges.iter().enumerate().find(|&(other, outer)| {
+            other != idx && outer.start() <= range.start() && range.end() <= outer.end()
+        });
+        if let Some((_, outer)) = outer {
+            let label = format!("contained in {}-{}", outer.start(), outer.end());
+            match color {
+                true => write!(out, "  {CONTAINED_COLOR}{label}{RESET_COLOR}").unwrap(),
+                false => write!(out, "  ({label})").unwrap(),
+            }
+        }
+        out.push('\n');
+    }
+    if !color && !shared.is_empty() {
+        let marks: String = overlapping
+            .iter()
+            .map(|&overlap| if overlap { '^' } else { ' ' })
+            .collect();
+        writeln!(out, "{marks}  overlap {shared}").unwrap();
+    }
+    out
+}
+
+/// Draws the lines of the input selected by their number (from one), or all of them, on a
+/// common timeline of at most `width` columns.
+fn render_timeline(
+    input: &str,
+    lines: Option<&IntervalSet<usize>>,
+    width: usize,
+    color: bool,
+) -> Result<String, ParseError> {
+    let lines = input
+        .lines()
+        .enumerate()
+        .filter(|(idx, _)| lines.is_none_or(|lines| lines.contains(idx + 1)))
+        .map(|(idx, line)| parse_line(idx + 1, line))
+        .collect::<Result<Vec<_>, _>>()?;
+    let Some(timeline) = Timeline::new(&lines, width) else {
+        return Ok(String::new());
+    };
+    let mut out = String::new();
+    if timeline.scale > 1 {
+        let last = *timeline.sections(timeline.columns - 1).end();
+        writeln!(
+            out,
+            "Sections {}-{last}, {} per column\n",
+            timeline.first, timeline.scale
+        )
+        .unwrap();
+    }
+    for (idx, ranges) in lines.iter().enumerate() {
+        if idx > 0 {
+            out.push('\n');
+        }
+        out.push_str(&render_line(ranges, &timeline, color));
+    }
+    Ok(out)
 }
 
 #[cfg(test)]
 mod test {
-    use crate::{completely_contains, contains, parse_pairs, parse_range};
+    use crate::{
+        completely_contains, contains, drop_overlaps, format_ranges, parse_assignments,
+        parse_lines, parse_ranges, render_timeline, sections, shrink_overlaps, solve, OverlapIndex,
+        ShrinkError, MAX_SHRINK,
+    };
+    use aoc_2022::interval::IntervalSet;
+    use std::ops::RangeInclusive;
+
+    fn ranges(line: &str) -> Vec<RangeInclusive<usize>> {
+        parse_ranges(line).unwrap()
+    }
+
+    fn set(line: &str) -> IntervalSet<usize> {
+        line.parse().unwrap()
+    }
+
+    fn overlap(a: &RangeInclusive<usize>, b: &RangeInclusive<usize>) -> bool {
+        contains(&IntervalSet::from(a.clone()), &IntervalSet::from(b.clone()))
+    }
+
     #[test]
     fn text_parse_range() {
-        assert_eq!(parse_range("2-4"), 2..=4);
-        assert_eq!(parse_range("2-3"), 2..=3);
-        assert_eq!(parse_range("5-7"), 5..=7);
+        assert_eq!(ranges("2-4"), [2..=4]);
+        assert_eq!(ranges("2-3"), [2..=3]);
+        assert_eq!(ranges("5"), [5..=5]);
     }
 
     #[test]
     fn text_parse_pairs() {
-        assert_eq!(parse_pairs("2-4,6-8"), (2..=4, 6..=8));
-        assert_eq!(parse_pairs("2-3,4-5"), (2..=3, 4..=5));
-        assert_eq!(parse_pairs("5-7,7-9"), (5..=7, 7..=9));
-        assert_eq!(parse_pairs("6-6,4-6"), (6..=6, 4..=6));
-        assert_eq!(parse_pairs("2-8,3-7"), (2..=8, 3..=7));
+        assert_eq!(ranges("2-4,6-8"), [2..=4, 6..=8]);
+        assert_eq!(ranges("2-3,4-5"), [2..=3, 4..=5]);
+        assert_eq!(ranges("5-7,7-9"), [5..=7, 7..=9]);
+        assert_eq!(ranges("6-6,4-6"), [6..=6, 4..=6]);
+        assert_eq!(ranges("2-8,3-7"), [2..=8, 3..=7]);
+        assert_eq!(ranges("1-2,4-5,2-3"), [1..=2, 4..=5, 2..=3]);
+    }
+
+    #[test]
+    fn test_invalid_lines() {
+        let error = |input: &str| parse_lines(input).unwrap_err().to_string();
+        assert_eq!(
+            error("2-4,6-8\n2-x,3-4"),
+            "line 2: invalid number `x` in `2-x`"
+        );
+        assert_eq!(
+            error("2-4,8-6"),
+            "line 1: range `8-6` ends before it starts"
+        );
+        assert_eq!(
+            error("2-4,6-8\n\n1-2,3-4"),
+            "line 2: invalid number `` in ``"
+        );
+        assert!(parse_assignments("1-2,-3").is_err());
+        assert!(render_timeline("1-2\n1-?", None, 100, false).is_err());
+        // Only the drawn lines are read.
+        assert!(render_timeline("1-2\n1-?", Some(&"1".parse().unwrap()), 100, false).is_ok());
     }
 
     #[test]
     fn text_completely_contains() {
-        assert_eq!(completely_contains(&(2..=4), &(6..=8)), false);
-        assert_eq!(completely_contains(&(2..=3), &(4..=5)), false);
-        assert_eq!(completely_contains(&(5..=7), &(7..=9)), false);
-        assert_eq!(completely_contains(&(6..=6), &(4..=6)), true);
-        assert_eq!(completely_contains(&(2..=8), &(3..=7)), true);
+        assert!(!completely_contains(&set("2-4"), &set("6-8")));
+        assert!(!completely_contains(&set("2-3"), &set("4-5")));
+        assert!(!completely_contains(&set("5-7"), &set("7-9")));
+        assert!(completely_contains(&set("6-6"), &set("4-6")));
+        assert!(completely_contains(&set("2-8"), &set("3-7")));
     }
     #[test]
     fn text_contains() {
-        assert_eq!(contains(&(2..=4), &(6..=8)), false);
-        assert_eq!(contains(&(2..=8), &(3..=7)), true);
-        assert_eq!(contains(&(6..=6), &(4..=6)), true);
-        assert_eq!(contains(&(2..=6), &(4..=8)), true);
+        assert!(!contains(&set("2-4"), &set("6-8")));
+        assert!(contains(&set("2-8"), &set("3-7")));
+        assert!(contains(&set("6-6"), &set("4-6")));
+        assert!(contains(&set("2-6"), &set("4-8")));
+    }
+
+    #[test]
+    fn test_more_ranges() {
+        assert_eq!(solve("1-2,4-5,2-3\n1-2,4-5,7-9\n1-9,4-5,11-12"), ["1", "2"]);
+    }
+
+    #[test]
+    fn test_overlap_index() {
+        let input = "2-4,6-8\n2-3,4-5\n5-7,7-9\n2-8,3-7\n6-6,4-6\n2-6,4-8";
+        let index = OverlapIndex::new(parse_assignments(input).unwrap());
+        let mut pairs = Vec::new();
+        index.for_each_overlapping_pair(|a, b| pairs.push((a.clone(), b.clone())));
+        assert_eq!(pairs.len(), index.count_overlapping_pairs());
+        // Section 6 is covered by eight of the twelve assignments, section 4 by seven.
+        assert_eq!(index.max_coverage(), Some((6, 8)));
+        assert_eq!(index.covering(4).len(), 7);
+        let covering: Vec<_> = index.covering(6).iter().map(|a| a.to_string()).collect();
+        assert_eq!(covering.len(), 8);
+        assert!(covering.contains(&"line 5 elf 1 (6-6)".to_owned()));
+        assert!(index.covering(1).is_empty());
+        assert_eq!(index.covering(9).len(), 1);
+
+        // Compare with checking every pair.
+        let all = parse_assignments(input).unwrap();
+        let expected = (0..all.len())
+            .flat_map(|a| (a + 1..all.len()).map(move |b| (a, b)))
+            .filter(|&(a, b)| overlap(&all[a].sections, &all[b].sections))
+            .count();
+        assert_eq!(pairs.len(), expected);
+        assert!(pairs.iter().all(|(a, b)| overlap(&a.sections, &b.sections)));
+
+        assert_eq!(OverlapIndex::new(Vec::new()).max_coverage(), None);
+    }
+
+    #[test]
+    fn test_drop_overlaps() {
+        assert_eq!(drop_overlaps(&ranges("2-4,6-8")), [2..=4, 6..=8]);
+        assert_eq!(drop_overlaps(&ranges("2-8,3-7")), [3..=7]);
+        // Keeping the two short ranges beats keeping the long one.
+        assert_eq!(
+            drop_overlaps(&ranges("1-10,1-3,5-9,4-4")),
+            [1..=3, 5..=9, 4..=4]
+        );
+        assert_eq!(format_ranges(&drop_overlaps(&ranges("5-7,7-9"))), "5-7");
+    }
+
+    #[test]
+    fn test_shrink_overlaps() {
+        assert_eq!(shrink_overlaps(&ranges("2-4,6-8")), Ok(vec![2..=4, 6..=8]));
+        // Dropping the shared section 7 from either range.
+        assert_eq!(
+            shrink_overlaps(&ranges("5-7,7-9")).map(|r| format_ranges(&r)),
+            Ok("5-6,7-9".to_owned())
+        );
+        // The outer range keeps a side of two sections (or 3-7 shrinks to 3), rather than 2-2.
+        let shrunk = shrink_overlaps(&ranges("2-9,3-7")).unwrap();
+        assert_eq!(sections(&shrunk), 7);
+        assert!(!overlap(&shrunk[0], &shrunk[1]));
+        assert_eq!(
+            shrink_overlaps(&ranges("1-10,1-10,1-10")).map(|r| format_ranges(&r)),
+            Ok("1-1,2-2,3-10".to_owned())
+        );
+        assert_eq!(
+            shrink_overlaps(&ranges("3-3,3-3")),
+            Err(ShrinkError::Impossible)
+        );
+        // Too many ranges to search, even though they could be shrunk.
+        let many = vec!["1-20"; MAX_SHRINK + 1].join(",");
+        assert_eq!(
+            shrink_overlaps(&ranges(&many)),
+            Err(ShrinkError::TooManyRanges(MAX_SHRINK + 1))
+        );
+        let three = shrink_overlaps(&ranges("1-5,2-6,3-7")).unwrap();
+        assert_eq!(sections(&three), 7);
+        assert!(!overlap(&three[0], &three[1]) && !overlap(&three[1], &three[2]));
+        assert!(!overlap(&three[0], &three[2]));
+        // Sections beyond `i64::MAX` are valid too.
+        let big = shrink_overlaps(&ranges(
+            "9223372036854775808-9223372036854775810,9223372036854775809-9223372036854775811",
+        ))
+        .unwrap();
+        assert_eq!(sections(&big), 4);
+        assert!(!overlap(&big[0], &big[1]));
+        let top = shrink_overlaps(&[usize::MAX..=usize::MAX, 0..=usize::MAX]).unwrap();
+        assert_eq!(top, [usize::MAX..=usize::MAX, 0..=usize::MAX - 1]);
+    }
+
+    #[test]
+    fn test_render_timeline() {
+        let input = "2-4,6-8\n2-3,4-5\n5-7,7-9\n2-8,3-7\n6-6,4-6\n2-6,4-8";
+        let lines = "1,4-5".parse().unwrap();
+        assert_eq!(
+            render_timeline(input, Some(&lines), 100, false).unwrap(),
+            ".234.....  2-4\n\
+             .....678.  6-8\n\
+             \n\
+             .2345678.  2-8\n\
+             ..34567..  3-7  (contained in 2-8)\n\
+             \x20 ^^^^^    overlap 3-7\n\
+             \n\
+             .....6...  6-6  (contained in 4-6)\n\
+             ...456...  4-6\n\
+             \x20    ^     overlap 6\n"
+        );
+        let colored = render_timeline("5-7,7-9", None, 100, true).unwrap();
+        assert_eq!(
+            colored.lines().next(),
+            Some(".56\x1b[1;33m7\x1b[0m...  5-7")
+        );
+        assert_eq!(
+            render_timeline(input, Some(&"10".parse().unwrap()), 100, false).unwrap(),
+            ""
+        );
+
+        // 0-1000 with a free section after it spans 1002 sections, 101 per column.
+        let scaled = render_timeline("0-1000,900-950", None, 10, false).unwrap();
+        let rows: Vec<_> = scaled.lines().collect();
+        assert_eq!(rows[0], "Sections 0-1009, 101 per column");
+        assert_eq!(rows[2], "##########  0-1000");
+        assert_eq!(rows[3], "........##  900-950  (contained in 0-1000)");
+        assert_eq!(rows[4], "        ^^  overlap 900-950");
     }
 }
@@ -1,78 +1,545 @@
+use aoc_2022::{batch, config::Config};
 use lazy_regex::regex_captures;
-use std::path::Path;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt::{Display, Formatter};
 
 fn main() {
+    let day = aoc_2022::day_name(file!());
+    let config = Config::from_env();
+    if let Some(pattern) = config.args().value("batch") {
+        batch::run(&config, day, pattern, solve);
+        return;
+    }
+
     // Read input file
-    let day = Path::new(file!()).file_stem().unwrap().to_str().unwrap();
-    let input = std::fs::read_to_string(format!("inputs/{day}.txt")).unwrap();
+    let input = config.read_input(day);
 
-    let (stacks_s, instructions_s) = input.split_once("\n\n").unwrap();
-    let instructions: Vec<Instruction> = instructions_s.lines().map(parse_instruction).collect();
-    let initial_state = parse_stacks(stacks_s);
+    // Skip the instructions that cannot be performed, or move the crates there are when a
+    // stack holds too few, instead of stopping at the first one.
+    let lenient = config.args().flag("lenient");
 
-    let result = instructions
-        .iter()
-        .fold(initial_state.clone(), perform_instruction)
-        .into_iter()
-        .filter_map(|v| v.last().copied())
-        .collect::<String>();
+    // Draw the stacks after every instruction, `--replay --crane 9001` to replay with another
+    // crane than the CrateMover 9000.
+    if config.args().flag("replay") {
+        let spec = config.args().value("crane").unwrap_or("9000");
+        let mut crane = parse_crane(spec).unwrap_or_else(|e| panic!("invalid --crane: {e}"));
+        let (drawing, _) = input.split_once("\n\n").unwrap_or((&input, ""));
+        println!("{}", render_stacks(&parse_stacks(drawing)));
+        let execution = execute(crane.as_mut(), &input, lenient, |instruction, state| {
+            println!("\n{instruction}\n{}", render_stacks(state));
+        });
+        report(&crane.name(), execution);
+        return;
+    }
 
-    println!("Solution part 1 : {result}");
+    // Find the fewest instructions from the drawing of the input to the one of `--plan path`,
+    // printed as an input of the day, e.g. `--plan target.txt --crane 9001`.
+    if let Some(path) = config.args().value("plan") {
+        let spec = config.args().value("crane").unwrap_or("9000");
+        if spec == "alternating" {
+            panic!("invalid --crane: the alternating crane cannot be planned for");
+        }
+        let mut crane = parse_crane(spec).unwrap_or_else(|e| panic!("invalid --crane: {e}"));
+        let target =
+            std::fs::read_to_string(path).unwrap_or_else(|e| panic!("could not read {path}: {e}"));
+        let drawing = |input: &str| parse_stacks(input.split_once("\n\n").map_or(input, |s| s.0));
+        let (initial, target) = (drawing(&input), drawing(&target));
+        let limit = config.day_param(day, "plan_limit", 1_000_000);
+        match plan(crane.as_mut(), &initial, &target, limit) {
+            Ok(instructions) => {
+                println!("{}\n", render_stacks(&initial));
+                for instruction in &instructions {
+                    println!("{instruction}");
+                }
+                eprintln!(
+                    "{} instructions with the {}",
+                    instructions.len(),
+                    crane.name()
+                );
+            }
+            Err(e) => panic!("{e}"),
+        }
+        return;
+    }
 
-    let result = instructions
-        .iter()
-        .fold(initial_state, perform_instruction_9001)
-        .into_iter()
-        .filter_map(|v| v.last().copied())
-        .collect::<String>();
+    // Compare the tops left by cranes, e.g. `--crane 9000,capacity:2`, or by all the cranes with
+    // `--compare-cranes`. With just `--lenient`, the cranes of both parts are compared.
+    let specs = config.args().value("crane");
+    if specs.is_some() || config.args().flag("compare-cranes") || lenient {
+        let cranes: Vec<Box<dyn Crane>> = match specs {
+            Some(specs) => specs
+                .split(',')
+                .map(|spec| parse_crane(spec).unwrap_or_else(|e| panic!("invalid --crane: {e}")))
+                .collect(),
+            None if config.args().flag("compare-cranes") => {
+                all_cranes(config.day_param(day, "crane_capacity", 3))
+            }
+            None => vec![Box::new(CrateMover9000), Box::new(CrateMover9001)],
+        };
+        for mut crane in cranes {
+            let execution = execute(crane.as_mut(), &input, lenient, |_, _| {});
+            let state = report(&crane.name(), execution);
+            println!("{:<24} {}", crane.name(), tops(&state));
+        }
+        return;
+    }
+    let answers = solve(&input);
 
-    println!("Solution part 2 : {result}");
+    println!("Solution part 1 : {}", answers[0]);
+    println!("Solution part 2 : {}", answers[1]);
 }
 
-#[derive(PartialEq, Debug)]
+/// Prints the warnings of an execution to stderr and returns the final state, panics with the
+/// error of a failed one.
+fn report(crane: &str, execution: Result<Execution, InstructionError>) -> Vec<Vec<char>> {
+    let execution = execution.unwrap_or_else(|e| panic!("{crane}: {e}"));
+    for warning in &execution.warnings {
+        eprintln!("{crane}: {warning}");
+    }
+    execution.state
+}
+
+fn solve(input: &str) -> Vec<String> {
+    let tops_with = |crane: &mut dyn Crane| match execute(crane, input, false, |_, _| {}) {
+        Ok(execution) => tops(&execution.state),
+        Err(e) => panic!("{e}"),
+    };
+    let part1 = tops_with(&mut CrateMover9000);
+    let part2 = tops_with(&mut CrateMover9001);
+
+    vec![part1, part2]
+}
+
+/// The crates on top of the stacks, empty stacks are left out.
+fn tops(state: &[Vec<char>]) -> String {
+    state.iter().filter_map(|v| v.last().copied()).collect()
+}
+
+#[derive(PartialEq, Debug, Clone)]
 struct Instruction {
     count: usize,
     from: usize,
     to: usize,
 }
 
-fn perform_instruction(mut state: Vec<Vec<char>>, instruction: &Instruction) -> Vec<Vec<char>> {
-    for _i in 0..instruction.count {
-        let value = state[instruction.from].pop().unwrap();
-        state[instruction.to].push(value);
+/// Formats the instruction as in the input, with stacks numbered from one.
+impl Display for Instruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "move {} from {} to {}",
+            self.count,
+            self.from + 1,
+            self.to + 1
+        )
     }
+}
 
-    state
+/// A crane moving crates between the stacks, as the instructions say.
+trait Crane {
+    fn name(&self) -> String;
+    /// Performs an instruction, a crane may depend on the instructions it performed before.
+    fn perform(&mut self, state: Vec<Vec<char>>, instruction: &Instruction) -> Vec<Vec<char>>;
 }
 
-fn perform_instruction_9001(
-    mut state: Vec<Vec<char>>,
-    instruction: &Instruction,
-) -> Vec<Vec<char>> {
-    let from = &mut state[instruction.from];
-    let from_len = from.len();
-    let pick = from[from_len - instruction.count..].to_vec();
-    from.truncate(from_len - instruction.count);
+/// The crane of part one, it lifts one crate at a time.
+struct CrateMover9000;
 
-    state[instruction.to].extend_from_slice(&pick);
+impl Crane for CrateMover9000 {
+    fn name(&self) -> String {
+        "CrateMover 9000".to_owned()
+    }
 
-    state
+    fn perform(&mut self, mut state: Vec<Vec<char>>, instruction: &Instruction) -> Vec<Vec<char>> {
+        for _i in 0..instruction.count {
+            let value = state[instruction.from].pop().unwrap();
+            state[instruction.to].push(value);
+        }
+
+        state
+    }
 }
 
-fn parse_instruction(line: &str) -> Instruction {
-    let (_, count, from, to) = regex_captures!(r#"move (\d*) from (\d*) to (\d*)$"#, line).unwrap();
-    // Minus one as we use the index of a vector which starts at 0 instead of 1.
-    Instruction {
-        count: count.parse::<usize>().unwrap(),
-        from: from.parse::<usize>().unwrap() - 1,
-        to: to.parse::<usize>().unwrap() - 1,
+/// The crane of part two, it lifts all the crates at once and keeps their order.
+struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn name(&self) -> String {
+        "CrateMover 9001".to_owned()
+    }
+
+    fn perform(&mut self, mut state: Vec<Vec<char>>, instruction: &Instruction) -> Vec<Vec<char>> {
+        let from = &mut state[instruction.from];
+        let from_len = from.len();
+        let pick = from[from_len - instruction.count..].to_vec();
+        from.truncate(from_len - instruction.count);
+
+        state[instruction.to].extend_from_slice(&pick);
+
+        state
+    }
+}
+
+/// A crane lifting at most `capacity` crates at once, which keep their order.
+///
+/// With a capacity of one it moves crates as the CrateMover 9000, with a capacity as large as
+/// the stacks as the CrateMover 9001.
+struct LimitedCrane {
+    capacity: usize,
+}
+
+impl Crane for LimitedCrane {
+    fn name(&self) -> String {
+        format!("capacity {}", self.capacity)
+    }
+
+    fn perform(&mut self, mut state: Vec<Vec<char>>, instruction: &Instruction) -> Vec<Vec<char>> {
+        let mut remaining = instruction.count;
+        while remaining > 0 {
+            let count = remaining.min(self.capacity);
+            lift(&mut state, instruction.from, instruction.to, count, false);
+            remaining -= count;
+        }
+        state
+    }
+}
+
+/// A crane lifting all the crates at once, which puts them down in reverse order every other
+/// lift, starting with the second one.
+#[derive(Default)]
+struct AlternatingCrane {
+    lifts: usize,
+}
+
+impl Crane for AlternatingCrane {
+    fn name(&self) -> String {
+        "alternating".to_owned()
+    }
+
+    fn perform(&mut self, mut state: Vec<Vec<char>>, instruction: &Instruction) -> Vec<Vec<char>> {
+        let reverse = self.lifts % 2 == 1;
+        self.lifts += 1;
+        lift(
+            &mut state,
+            instruction.from,
+            instruction.to,
+            instruction.count,
+            reverse,
+        );
+        state
+    }
+}
+
+/// Moves the top `count` crates of a stack onto another one at once.
+fn lift(state: &mut [Vec<char>], from: usize, to: usize, count: usize, reverse: bool) {
+    let from_len = state[from].len();
+    let mut pick = state[from].split_off(from_len - count);
+    if reverse {
+        pick.reverse();
+    }
+    state[to].extend(pick);
+}
+
+/// Parses `9000`, `9001`, `capacity:N` or `alternating`.
+fn parse_crane(spec: &str) -> Result<Box<dyn Crane>, String> {
+    match spec.trim().split_once(':') {
+        None if spec.trim() == "9000" => Ok(Box::new(CrateMover9000)),
+        None if spec.trim() == "9001" => Ok(Box::new(CrateMover9001)),
+        None if spec.trim() == "alternating" => Ok(Box::new(AlternatingCrane::default())),
+        Some(("capacity", capacity)) => match capacity.parse() {
+            Ok(capacity) if capacity > 0 => Ok(Box::new(LimitedCrane { capacity })),
+            _ => Err(format!("invalid capacity `{capacity}`")),
+        },
+        _ => Err(format!("unknown crane `{spec}`")),
+    }
+}
+
+/// Every kind of crane, the limited one with the given capacity.
+fn all_cranes(capacity: usize) -> Vec<Box<dyn Crane>> {
+    vec![
+        Box::new(CrateMover9000),
+        Box::new(CrateMover9001),
+        Box::new(LimitedCrane { capacity }),
+        Box::new(AlternatingCrane::default()),
+    ]
+}
+
+/// Why an instruction cannot be performed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum MoveError {
+    /// The line is not `move N from A to B`.
+    Syntax,
+    /// Stacks are numbered from one up to the number of stacks.
+    NoSuchStack(usize),
+    NotEnoughCrates {
+        stack: usize,
+        available: usize,
+        count: usize,
+    },
+}
+
+impl Display for MoveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MoveError::Syntax => write!(f, "expected `move N from A to B`"),
+            MoveError::NoSuchStack(stack) => write!(f, "there is no stack {stack}"),
+            MoveError::NotEnoughCrates {
+                stack,
+                available,
+                count,
+            } => write!(
+                f,
+                "stack {stack} holds {available} crates, cannot move {count}"
+            ),
+        }
+    }
+}
+
+/// An instruction that cannot be performed, `line` numbers the lines of the whole input from one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct InstructionError {
+    line: usize,
+    text: String,
+    reason: MoveError,
+    /// The stacks the instruction moves from and to, by number, as they were before it.
+    stacks: Vec<(usize, Vec<char>)>,
+}
+
+impl Display for InstructionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: `{}`: {}", self.line, self.text, self.reason)?;
+        for (stack, crates) in &self.stacks {
+            let crates: Vec<_> = crates.iter().map(|c| format!("[{c}]")).collect();
+            match crates.is_empty() {
+                true => write!(f, "\n  stack {stack}: empty")?,
+                false => write!(f, "\n  stack {stack}: {}", crates.join(" "))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An instruction a lenient execution could not perform, and the crates it moved instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Warning {
+    error: InstructionError,
+    moved: usize,
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.moved {
+            0 => write!(f, "skipped {}", self.error),
+            moved => write!(f, "moved {moved} crates for {}", self.error),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Execution {
+    state: Vec<Vec<char>>,
+    warnings: Vec<Warning>,
+}
+
+/// Checks that the stacks of an instruction exist and hold enough crates.
+fn validate(instruction: &Instruction, state: &[Vec<char>]) -> Result<(), MoveError> {
+    for stack in [instruction.from, instruction.to] {
+        if stack >= state.len() {
+            return Err(MoveError::NoSuchStack(stack + 1));
+        }
+    }
+    let available = state[instruction.from].len();
+    if available < instruction.count {
+        return Err(MoveError::NotEnoughCrates {
+            stack: instruction.from + 1,
+            available,
+            count: instruction.count,
+        });
+    }
+    Ok(())
+}
+
+/// Performs the instructions of an input, calling `on_step` with every instruction performed
+/// and the state after it.
+///
+/// Stops at the first instruction that cannot be performed, unless `lenient`: then bad lines
+/// are skipped and moves from stacks with too few crates move the crates there are.
+fn execute(
+    crane: &mut dyn Crane,
+    input: &str,
+    lenient: bool,
+    mut on_step: impl FnMut(&Instruction, &[Vec<char>]),
+) -> Result<Execution, InstructionError> {
+    let (drawing, instructions) = input.split_once("\n\n").unwrap_or((input, ""));
+    // The drawing and the empty line come before the first instruction.
+    let first_line = drawing.lines().count() + 2;
+    let mut state = parse_stacks(drawing);
+    let mut warnings = Vec::new();
+    for (idx, line) in instructions.lines().enumerate() {
+        let checked = match parse_instruction(line) {
+            Ok(instruction) => match validate(&instruction, &state) {
+                Ok(()) => Ok(instruction),
+                Err(reason) => Err((reason, Some(instruction))),
+            },
+            Err(reason) => Err((reason, None)),
+        };
+        let instruction = match checked {
+            Ok(instruction) => instruction,
+            Err((reason, instruction)) => {
+                let mut stacks = Vec::new();
+                for stack in instruction.iter().flat_map(|i| [i.from, i.to]) {
+                    if stack < state.len() && stacks.iter().all(|(n, _)| *n != stack + 1) {
+                        stacks.push((stack + 1, state[stack].clone()));
+                    }
+                }
+                let moved = match reason {
+                    MoveError::NotEnoughCrates { available, .. } => available,
+                    _ => 0,
+                };
+                let error = InstructionError {
+                    line: first_line + idx,
+                    text: line.to_owned(),
+                    reason,
+                    stacks,
+                };
+                if !lenient {
+                    return Err(error);
+                }
+                warnings.push(Warning { error, moved });
+                // Move the crates there are, skip the other bad instructions.
+                match instruction {
+                    Some(instruction) if moved > 0 => Instruction {
+                        count: moved,
+                        ..instruction
+                    },
+                    _ => continue,
+                }
+            }
+        };
+        state = crane.perform(state, &instruction);
+        on_step(&instruction, &state);
+    }
+    Ok(Execution { state, warnings })
+}
+
+/// Why no instructions were found from one drawing to the other.
+#[derive(Debug, PartialEq, Eq)]
+enum PlanError {
+    /// No instructions lead to the target, e.g. its stacks or crates differ.
+    Unreachable,
+    /// The search stopped after visiting this many states.
+    Limit(usize),
+}
+
+impl Display for PlanError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlanError::Unreachable => write!(f, "the target cannot be reached"),
+            PlanError::Limit(limit) => write!(f, "gave up after visiting {limit} states"),
+        }
+    }
+}
+
+/// A lower bound of the instructions left: every stack with a misplaced crate must be lifted
+/// from, and every stack missing crates must be put onto, and each instruction does one of each.
+fn remaining_moves(state: &[Vec<char>], target: &[Vec<char>]) -> usize {
+    let (mut lifts, mut drops) = (0, 0);
+    for (stack, goal) in state.iter().zip(target) {
+        let placed = stack.iter().zip(goal).take_while(|(a, b)| a == b).count();
+        lifts += usize::from(placed < stack.len());
+        drops += usize::from(placed < goal.len());
+    }
+    lifts.max(drops)
+}
+
+/// Finds the fewest instructions that move the crates from `initial` to `target` with an A*
+/// search, visiting at most `limit` states.
+///
+/// The crane must not depend on the instructions it performed before.
+fn plan(
+    crane: &mut dyn Crane,
+    initial: &[Vec<char>],
+    target: &[Vec<char>],
+    limit: usize,
+) -> Result<Vec<Instruction>, PlanError> {
+    let crates = |state: &[Vec<char>]| {
+        let mut crates: Vec<char> = state.iter().flatten().copied().collect();
+        crates.sort_unstable();
+        crates
+    };
+    if initial.len() != target.len() || crates(initial) != crates(target) {
+        return Err(PlanError::Unreachable);
+    }
+
+    // The states found so far, and the state and instruction each was last reached from.
+    let mut states = vec![initial.to_vec()];
+    let mut parents: Vec<Option<(usize, Instruction)>> = vec![None];
+    let mut moves = HashMap::from([(initial.to_vec(), 0)]);
+    let mut queue = BinaryHeap::from([Reverse((remaining_moves(initial, target), 0, 0))]);
+    let mut visited = 0;
+    while let Some(Reverse((_, done, idx))) = queue.pop() {
+        if moves[&states[idx]] < done {
+            continue;
+        }
+        if states[idx] == target {
+            let mut instructions = Vec::new();
+            let mut idx = idx;
+            while let Some((previous, instruction)) = &parents[idx] {
+                instructions.push(instruction.clone());
+                idx = *previous;
+            }
+            instructions.reverse();
+            return Ok(instructions);
+        }
+        visited += 1;
+        if visited > limit {
+            return Err(PlanError::Limit(limit));
+        }
+        for from in 0..target.len() {
+            for to in (0..target.len()).filter(|&to| to != from) {
+                for count in 1..=states[idx][from].len() {
+                    let instruction = Instruction { count, from, to };
+                    let next = crane.perform(states[idx].clone(), &instruction);
+                    if moves.get(&next).is_some_and(|&m| m <= done + 1) {
+                        continue;
+                    }
+                    moves.insert(next.clone(), done + 1);
+                    let estimate = done + 1 + remaining_moves(&next, target);
+                    queue.push(Reverse((estimate, done + 1, states.len())));
+                    states.push(next);
+                    parents.push(Some((idx, instruction)));
+                }
+            }
+        }
     }
+    Err(PlanError::Unreachable)
 }
 
+fn parse_instruction(line: &str) -> Result<Instruction, MoveError> {
+    let (_, count, from, to) =
+        regex_captures!(r#"move (\d+) from (\d+) to (\d+)$"#, line).ok_or(MoveError::Syntax)?;
+    let number = |n: &str| n.parse::<usize>().map_err(|_| MoveError::Syntax);
+    // Minus one as we use the index of a vector which starts at 0 instead of 1.
+    let index = |n: &str| {
+        let stack = number(n)?;
+        stack.checked_sub(1).ok_or(MoveError::NoSuchStack(stack))
+    };
+    Ok(Instruction {
+        count: number(count)?,
+        from: index(from)?,
+        to: index(to)?,
+    })
+}
+
+/// Reads the drawing of the stacks, a crate may be any character but whitespace.
 fn parse_stacks(stack_lines: &str) -> Vec<Vec<char>> {
-    let r = lazy_regex::regex!(r"(?:\s{3,3}|\[([A-Z])\])\s?");
-    let mut result = Vec::new();
-    for line in stack_lines.lines().rev().skip(1) {
+    let r = lazy_regex::regex!(r"(?:\s{3,3}|\[(\S)\])\s?");
+    let mut lines = stack_lines.lines().rev();
+    // The numbers of the footer also count the stacks that are empty in every line.
+    let footer = lines.next().unwrap_or_default();
+    let mut result = vec![Vec::new(); footer.split_whitespace().count()];
+    for line in lines {
         for (idx, stack) in r.captures_iter(line).enumerate() {
             if idx >= result.len() {
                 result.resize_with(idx + 1, Vec::new);
@@ -85,43 +552,76 @@ fn parse_stacks(stack_lines: &str) -> Vec<Vec<char>> {
     result
 }
 
+/// Draws the stacks as in the input, with the numbered footer, `parse_stacks` reads it back as
+/// long as no crate is whitespace, which would be drawn like a missing one.
+///
+/// Every line is as wide as the footer, crates are drawn as `[A]` and missing ones as spaces.
+fn render_stacks(state: &[Vec<char>]) -> String {
+    let height = state.iter().map(Vec::len).max().unwrap_or(0);
+    let mut lines: Vec<String> = (0..height)
+        .rev()
+        .map(|level| {
+            let cells: Vec<_> = state
+                .iter()
+                .map(|stack| match stack.get(level) {
+                    Some(c) => format!("[{c}]"),
+                    None => "   ".to_owned(),
+                })
+                .collect();
+            cells.join(" ")
+        })
+        .collect();
+    let footer: Vec<_> = (1..=state.len()).map(|n| format!("{n:^3}")).collect();
+    lines.push(footer.join(" "));
+    lines.join("\n")
+}
+
 #[cfg(test)]
 mod test {
-    use crate::{parse_instruction, parse_stacks, Instruction};
+    use crate::{
+        all_cranes, execute, parse_crane, parse_instruction, parse_stacks, plan, render_stacks,
+        solve, tops, Crane, CrateMover9000, CrateMover9001, Instruction, MoveError, PlanError,
+    };
+
+    const EXAMPLE: &str = "    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 \n\n\
+                           move 1 from 2 to 1\n\
+                           move 3 from 1 to 3\n\
+                           move 2 from 2 to 1\n\
+                           move 1 from 1 to 2\n";
 
     #[test]
     fn test_parse_instruction() {
         assert_eq!(
             parse_instruction("move 1 from 2 to 1"),
-            Instruction {
+            Ok(Instruction {
                 count: 1,
                 from: 1,
                 to: 0
-            }
+            })
         );
         assert_eq!(
             parse_instruction("move 3 from 1 to 3"),
-            Instruction {
+            Ok(Instruction {
                 count: 3,
                 from: 0,
                 to: 2
-            }
+            })
         );
         assert_eq!(
             parse_instruction("move 2 from 2 to 1"),
-            Instruction {
+            Ok(Instruction {
                 count: 2,
                 from: 1,
                 to: 0
-            }
+            })
         );
         assert_eq!(
             parse_instruction("move 1 from 1 to 2"),
-            Instruction {
+            Ok(Instruction {
                 count: 1,
                 from: 0,
                 to: 1
-            }
+            })
         );
     }
 
@@ -130,4 +630,193 @@ mod test {
         let stacks = parse_stacks("    [D]\n[N] [C]\n[Z] [M] [P]\n 1   2   3");
         assert_eq!(stacks, vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']]);
     }
+
+    #[test]
+    fn test_solve() {
+        assert_eq!(solve(EXAMPLE), ["CMZ", "MCD"]);
+    }
+
+    #[test]
+    fn test_cranes() {
+        let tops_with = |spec: &str| {
+            let mut crane = parse_crane(spec).unwrap();
+            tops(
+                &execute(crane.as_mut(), EXAMPLE, false, |_, _| {})
+                    .unwrap()
+                    .state,
+            )
+        };
+        assert_eq!(tops_with("capacity:1"), "CMZ");
+        assert_eq!(tops_with("capacity:3"), "MCD");
+        // The second move is reversed and the fourth moves a single crate.
+        assert_eq!(tops_with("alternating"), "MCZ");
+        assert_eq!(tops_with("capacity:2"), "MCZ");
+        assert!(parse_crane("capacity:0").is_err());
+        assert!(parse_crane("9002").is_err());
+
+        let names: Vec<_> = all_cranes(2).iter().map(|crane| crane.name()).collect();
+        assert_eq!(
+            names,
+            [
+                "CrateMover 9000",
+                "CrateMover 9001",
+                "capacity 2",
+                "alternating"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_stacks() {
+        let (drawing, _) = EXAMPLE.split_once("\n\n").unwrap();
+        assert_eq!(render_stacks(&parse_stacks(drawing)), drawing);
+        assert_eq!(
+            parse_instruction("move 3 from 1 to 3").unwrap().to_string(),
+            "move 3 from 1 to 3"
+        );
+
+        let states = [
+            vec![vec![], vec!['A'], vec![]],
+            vec![vec![], vec![]],
+            vec![vec!['X', 'Y', 'Z']],
+            (0..12).map(|n| vec!['A'; n]).collect(),
+            vec![vec!['a', '7'], vec!['[', ']', '#'], vec!['é']],
+            vec![],
+        ];
+        for state in states {
+            assert_eq!(parse_stacks(&render_stacks(&state)), state);
+        }
+        // A blank crate cannot be told from a missing one.
+        assert_ne!(parse_stacks(&render_stacks(&[vec![' ']])), [vec![' ']]);
+        assert_eq!(render_stacks(&[vec![], vec![]]), " 1   2 ");
+    }
+
+    #[test]
+    fn test_execute() {
+        let input = "    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 \n\n\
+                     move 1 from 2 to 1\n\
+                     move 4 from 1 to 3\n\
+                     move 1 from 0 to 2\n\
+                     move 1 from 4 to 1\n\
+                     jump\n\
+                     move 2 from 2 to 1\n";
+        let error = execute(&mut CrateMover9000, input, false, |_, _| {}).unwrap_err();
+        assert_eq!(error.line, 7);
+        assert_eq!(
+            error.to_string(),
+            "line 7: `move 4 from 1 to 3`: stack 1 holds 3 crates, cannot move 4\n  \
+             stack 1: [Z] [N] [D]\n  stack 3: [P]"
+        );
+
+        let mut steps = Vec::new();
+        let execution = execute(&mut CrateMover9000, input, true, |instruction, _| {
+            steps.push(instruction.to_string())
+        })
+        .unwrap();
+        assert_eq!(
+            steps,
+            [
+                "move 1 from 2 to 1",
+                "move 3 from 1 to 3",
+                "move 2 from 2 to 1"
+            ]
+        );
+        assert_eq!(
+            execution.state,
+            [vec!['C', 'M'], vec![], vec!['P', 'D', 'N', 'Z']]
+        );
+        let warnings: Vec<_> = execution
+            .warnings
+            .iter()
+            .map(|w| (w.error.line, w.moved, w.error.reason.clone()))
+            .collect();
+        assert_eq!(
+            warnings,
+            [
+                (
+                    7,
+                    3,
+                    MoveError::NotEnoughCrates {
+                        stack: 1,
+                        available: 3,
+                        count: 4
+                    }
+                ),
+                (8, 0, MoveError::NoSuchStack(0)),
+                (9, 0, MoveError::NoSuchStack(4)),
+                (10, 0, MoveError::Syntax),
+            ]
+        );
+        assert_eq!(
+            execution.warnings[2].to_string(),
+            "skipped line 9: `move 1 from 4 to 1`: there is no stack 4\n  stack 1: empty"
+        );
+    }
+
+    #[test]
+    fn test_plan() {
+        let (drawing, _) = EXAMPLE.split_once("\n\n").unwrap();
+        let initial = parse_stacks(drawing);
+        let cranes: [fn() -> Box<dyn Crane>; 2] =
+            [|| Box::new(CrateMover9000), || Box::new(CrateMover9001)];
+        for new_crane in cranes {
+            let target = execute(new_crane().as_mut(), EXAMPLE, false, |_, _| {})
+                .unwrap()
+                .state;
+            let instructions = plan(new_crane().as_mut(), &initial, &target, 100_000).unwrap();
+            assert!(instructions.len() <= 4);
+
+            // The plan is an input of the day again.
+            let lines: Vec<_> = instructions.iter().map(|i| i.to_string()).collect();
+            let input = format!("{}\n\n{}\n", render_stacks(&initial), lines.join("\n"));
+            let execution = execute(new_crane().as_mut(), &input, false, |_, _| {}).unwrap();
+            assert_eq!(execution.state, target);
+        }
+
+        let mut crane = CrateMover9000;
+        assert_eq!(plan(&mut crane, &initial, &initial, 10), Ok(vec![]));
+        // The CrateMover 9000 turns the stacks it moves upside down.
+        let flipped = [vec!['D', 'C', 'M'], vec![], vec!['P', 'N', 'Z']];
+        assert_eq!(
+            plan(&mut crane, &initial, &flipped, 100_000).map(|p| p.len()),
+            Ok(2)
+        );
+        assert!(
+            plan(&mut CrateMover9001, &initial, &flipped, 100_000)
+                .unwrap()
+                .len()
+                > 2
+        );
+        let upside_down = [vec!['A', 'B', 'C'], vec![]];
+        let instructions = plan(&mut crane, &upside_down, &[vec![], vec!['C', 'B', 'A']], 10);
+        assert_eq!(
+            instructions,
+            Ok(vec![Instruction {
+                count: 3,
+                from: 0,
+                to: 1
+            }])
+        );
+        // The CrateMover 9001 moves the crates one by one instead.
+        let instructions = plan(
+            &mut CrateMover9001,
+            &upside_down,
+            &[vec![], vec!['C', 'B', 'A']],
+            1000,
+        );
+        assert_eq!(instructions.map(|p| p.len()), Ok(3));
+        // A single stack cannot be changed.
+        assert_eq!(
+            plan(&mut crane, &[vec!['A', 'B']], &[vec!['B', 'A']], 10),
+            Err(PlanError::Unreachable)
+        );
+        assert_eq!(
+            plan(&mut crane, &initial, &[vec!['Z'], vec![], vec![]], 10),
+            Err(PlanError::Unreachable)
+        );
+        assert_eq!(
+            plan(&mut crane, &initial, &flipped, 1),
+            Err(PlanError::Limit(1))
+        );
+    }
 }
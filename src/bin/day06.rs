@@ -1,29 +1,45 @@
-use std::path::Path;
+use aoc_2022::{batch, config::Config};
 
 fn main() {
-    // Read input file
-    let day = Path::new(file!()).file_stem().unwrap().to_str().unwrap();
-    let input = std::fs::read_to_string(format!("inputs/{day}.txt")).unwrap();
+    let day = aoc_2022::day_name(file!());
+    let config = Config::from_env();
+    if let Some(pattern) = config.args().value("batch") {
+        batch::run(&config, day, pattern, solve);
+        return;
+    }
 
-    let result = find_marker_index(&input, 4);
-    println!("Solution 1: {result}");
+    // Read input file
+    let input = config.read_input(day);
+    let answers = solve(&input);
 
-    let result = find_marker_index(&input, 14);
-    println!("Solution 2: {result}");
+    println!("Solution 1: {}", answers[0]);
+    println!("Solution 2: {}", answers[1]);
 }
 
-fn find_marker_index(input: &str, window_size: usize) -> usize {
-    'outer: for i in 0..input.len() - window_size - 1 {
-        let window = &input[i..i + window_size];
-        for (idx, c) in window.chars().enumerate() {
-            if window[idx + 1..].contains(c) {
-                continue 'outer;
-            }
-        }
-        return i + window_size;
-    }
+fn solve(input: &str) -> Vec<String> {
+    let input = input.trim_end();
+    [4, 14]
+        .into_iter()
+        .map(|window_size| match find_marker_index(input, window_size) {
+            Some(index) => index.to_string(),
+            None => panic!("no marker of {window_size} distinct characters"),
+        })
+        .collect()
+}
 
-    unreachable!();
+/// Returns the number of characters processed when the first window of `window_size` distinct
+/// characters is complete, or `None` if the input does not contain such a window.
+fn find_marker_index(input: &str, window_size: usize) -> Option<usize> {
+    let chars: Vec<char> = input.chars().collect();
+    chars
+        .windows(window_size)
+        .position(|window| {
+            window
+                .iter()
+                .enumerate()
+                .all(|(idx, c)| !window[idx + 1..].contains(c))
+        })
+        .map(|i| i + window_size)
 }
 
 #[cfg(test)]
@@ -32,27 +48,53 @@ mod test {
 
     #[test]
     fn test_find_marker_index() {
-        assert_eq!(find_marker_index("bvwbjplbgvbhsrlpgdmjqwftvncz", 4), 5);
-        assert_eq!(find_marker_index("nppdvjthqldpwncqszvftbrmjlhg", 4), 6);
+        assert_eq!(
+            find_marker_index("bvwbjplbgvbhsrlpgdmjqwftvncz", 4),
+            Some(5)
+        );
+        assert_eq!(
+            find_marker_index("nppdvjthqldpwncqszvftbrmjlhg", 4),
+            Some(6)
+        );
         assert_eq!(
             find_marker_index("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 4),
-            10
+            Some(10)
+        );
+        assert_eq!(
+            find_marker_index("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 4),
+            Some(11)
         );
-        assert_eq!(find_marker_index("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 4), 11);
     }
 
     #[test]
     fn test_find_marker_index_14() {
-        assert_eq!(find_marker_index("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 14), 19);
-        assert_eq!(find_marker_index("bvwbjplbgvbhsrlpgdmjqwftvncz", 14), 23);
-        assert_eq!(find_marker_index("nppdvjthqldpwncqszvftbrmjlhg", 14), 23);
+        assert_eq!(
+            find_marker_index("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 14),
+            Some(19)
+        );
+        assert_eq!(
+            find_marker_index("bvwbjplbgvbhsrlpgdmjqwftvncz", 14),
+            Some(23)
+        );
+        assert_eq!(
+            find_marker_index("nppdvjthqldpwncqszvftbrmjlhg", 14),
+            Some(23)
+        );
         assert_eq!(
             find_marker_index("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 14),
-            29
+            Some(29)
         );
         assert_eq!(
             find_marker_index("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 14),
-            26
+            Some(26)
         );
     }
+
+    #[test]
+    fn test_find_marker_index_at_end() {
+        assert_eq!(find_marker_index("aaaabcd", 4), Some(7));
+        assert_eq!(find_marker_index("abcd", 4), Some(4));
+        assert_eq!(find_marker_index("aaaa", 4), None);
+        assert_eq!(find_marker_index("abc", 4), None);
+    }
 }
@@ -1,3 +1,4 @@
+use aoc_2022::{batch, checked, config::Config};
 use std::collections::HashMap;
 use std::fmt::{Debug, Formatter};
 use std::ops::{Index, IndexMut};
@@ -59,7 +60,7 @@ impl FileSystem {
             let mut size = 0;
             let tmp = self[path].children.values().copied().collect::<Vec<_>>();
             for child_entry_id in tmp {
-                size += self.entry_size(child_entry_id);
+                size = checked::add(size, self.entry_size(child_entry_id));
             }
             self[path].size = Some(size);
             size
@@ -138,11 +139,34 @@ impl IndexMut<EntryId> for FileSystem {
 }
 
 fn main() {
+    let day = aoc_2022::day_name(file!());
+    let config = Config::from_env();
+    let disk = Disk {
+        total_size_available: config.day_param(day, "total_size_available", 70000000),
+        needed_free_size: config.day_param(day, "needed_free_size", 30000000),
+    };
+    if config.args().flag("checked") {
+        checked::enable(day);
+    }
+    if let Some(pattern) = config.args().value("batch") {
+        batch::run(&config, day, pattern, |input| solve(input, &disk));
+        return;
+    }
+
     // Read input file
-    let day = Path::new(file!()).file_stem().unwrap().to_str().unwrap();
-    let binding = std::fs::read_to_string(format!("inputs/{day}.txt")).unwrap();
-    let input = binding.as_str();
+    let input = config.read_input(day);
+    if config.args().flag("tree") {
+        let mut fs = parse_file_system(&input);
+        fs.entry_size(fs.root());
+        println!("{:?}", fs);
+    }
+    let answers = solve(&input, &disk);
+
+    println!("Solution part 1: {}", answers[0]);
+    println!("Solution part 2: {}", answers[1]);
+}
 
+fn parse_file_system(input: &str) -> FileSystem {
     let mut fs = FileSystem::default();
     let mut current_dir = fs.root();
 
@@ -171,21 +195,28 @@ fn main() {
             unreachable!()
         }
     }
-    fs.entry_size(fs.root());
-    println!("{:?}", fs);
+    fs
+}
 
+/// Sizes of the disk the file system is stored on.
+struct Disk {
+    total_size_available: usize,
+    needed_free_size: usize,
+}
+
+fn solve(input: &str, disk: &Disk) -> Vec<String> {
+    let mut fs = parse_file_system(input);
     let dirs_sizes: Vec<_> = fs
         .dirs()
         .into_iter()
         .map(|entry_id| fs.entry_size(entry_id))
         .collect();
-    let total_size: usize = dirs_sizes.iter().filter(|size| **size < 100000).sum();
-
-    println!("Solution part 1: {}", total_size);
+    let total_size = checked::sum(0, dirs_sizes.iter().copied().filter(|size| *size < 100000));
 
-    let needed_free_size = 30000000;
-    let total_size_available = 70000000;
-    let total_size_to_remove = needed_free_size - (total_size_available - fs.entry_size(fs.root()));
+    let total_size_to_remove = checked::sub(
+        disk.needed_free_size,
+        checked::sub(disk.total_size_available, fs.entry_size(fs.root())),
+    );
 
     let dir_size_minimal_removal = dirs_sizes
         .iter()
@@ -193,5 +224,5 @@ fn main() {
         .min()
         .unwrap();
 
-    println!("Solution part 2: {dir_size_minimal_removal}");
+    vec![total_size.to_string(), dir_size_minimal_removal.to_string()]
 }
@@ -1,5 +1,5 @@
+use aoc_2022::{batch, config::Config};
 use grid::Grid;
-use std::path::Path;
 
 #[derive(Default, Clone)]
 struct Visibility {
@@ -16,10 +16,23 @@ impl Visibility {
 }
 
 fn main() {
+    let day = aoc_2022::day_name(file!());
+    let config = Config::from_env();
+    if let Some(pattern) = config.args().value("batch") {
+        batch::run(&config, day, pattern, solve);
+        return;
+    }
+
     // Read input file
-    let day = Path::new(file!()).file_stem().unwrap().to_str().unwrap();
-    let binding = std::fs::read_to_string(format!("inputs/{day}.txt")).unwrap();
-    let lines: Vec<_> = binding.lines().collect();
+    let input = config.read_input(day);
+    let answers = solve(&input);
+
+    println!("Solution part 1: {}", answers[0]);
+    println!("Solution part 2: {}", answers[1]);
+}
+
+fn solve(input: &str) -> Vec<String> {
+    let lines: Vec<_> = input.lines().collect();
 
     let width = lines[0].len();
     let height = lines.len();
@@ -90,16 +103,13 @@ fn main() {
         .filter(|vis| vis.is_visible())
         .count();
 
-    println!("{:?}", height_grid);
-    println!("Solution part 1: {}", visible_count);
-
     let highest_score = (0..height_grid.cols())
         .flat_map(|x| (0..height_grid.rows()).map(move |y| (x, y)))
         .map(|(x, y)| scenic_score(&height_grid, x, y))
         .max()
         .unwrap();
 
-    println!("Solution part 2: {}", highest_score);
+    vec![visible_count.to_string(), highest_score.to_string()]
 }
 
 fn scenic_score(height_grid: &Grid<usize>, x: usize, y: usize) -> usize {
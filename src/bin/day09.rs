@@ -1,7 +1,7 @@
+use aoc_2022::{batch, config::Config};
 use std::collections::HashSet;
 use std::iter;
 use std::ops::{Add, AddAssign};
-use std::path::Path;
 
 #[derive(Debug, Clone, Copy, Ord, PartialOrd, Eq, PartialEq, Hash)]
 struct Point {
@@ -32,10 +32,23 @@ struct Instruction {
 }
 
 fn main() {
+    let day = aoc_2022::day_name(file!());
+    let config = Config::from_env();
+    if let Some(pattern) = config.args().value("batch") {
+        batch::run(&config, day, pattern, solve);
+        return;
+    }
+
     // Read input file
-    let day = Path::new(file!()).file_stem().unwrap().to_str().unwrap();
-    let input = std::fs::read_to_string(format!("inputs/{day}.txt")).unwrap();
+    let input = config.read_input(day);
+    let answers = solve(&input);
+
+    // Output result
+    println!("Solution 1: {}", answers[0]);
+    println!("Solution 2: {}", answers[1]);
+}
 
+fn solve(input: &str) -> Vec<String> {
     let instructions: Vec<Instruction> = input
         .lines()
         .map(|line| {
@@ -61,15 +74,14 @@ fn main() {
     unique_positions.insert(tail_pos);
     for delta in instructions
         .iter()
-        .flat_map(|instr| iter::repeat(instr.direction).take(instr.steps))
+        .flat_map(|instr| iter::repeat_n(instr.direction, instr.steps))
     {
         head_pos += delta;
         tail_pos = compute_tail_position(head_pos, tail_pos);
         unique_positions.insert(tail_pos);
     }
 
-    // Output result
-    println!("Solution 1: {}", unique_positions.len());
+    let part1 = unique_positions.len();
 
     // Iterate over all instructions
     let mut unique_positions = HashSet::new();
@@ -78,7 +90,7 @@ fn main() {
     unique_positions.insert(rope_pos[8]);
     for delta in instructions
         .iter()
-        .flat_map(|instr| iter::repeat(instr.direction).take(instr.steps))
+        .flat_map(|instr| iter::repeat_n(instr.direction, instr.steps))
     {
         head_pos += delta;
         for i in 0..9 {
@@ -89,7 +101,7 @@ fn main() {
         unique_positions.insert(rope_pos[8]);
     }
 
-    println!("Solution 2: {}", unique_positions.len());
+    vec![part1.to_string(), unique_positions.len().to_string()]
 }
 
 fn compute_tail_position(head: Point, tail: Point) -> Point {
@@ -1,3 +1,5 @@
+use aoc_2022::{batch, checked, config::Config};
+
 enum Op {
     Noop,
     AddX(isize),
@@ -20,14 +22,35 @@ impl Op {
 }
 
 fn main() {
+    let day = aoc_2022::day_name(file!());
+    let config = Config::from_env();
+    let crt = Crt {
+        width: config.day_param(day, "crt_width", 40),
+        height: config.day_param(day, "crt_height", 6),
+    };
+    if config.args().flag("checked") {
+        checked::enable(day);
+    }
+    if let Some(pattern) = config.args().value("batch") {
+        batch::run(&config, day, pattern, |input| solve(input, &crt));
+        return;
+    }
+
     // Read input file
-    let day = std::path::Path::new(file!())
-        .file_stem()
-        .unwrap()
-        .to_str()
-        .unwrap();
-    let input = std::fs::read_to_string(format!("inputs/{day}.txt")).unwrap();
+    let input = config.read_input(day);
+    let answers = solve(&input, &crt);
 
+    println!("Solution 1: {}", answers[0]);
+    print!("{}", answers[1]);
+}
+
+/// Dimensions of the CRT screen in pixels.
+struct Crt {
+    width: usize,
+    height: usize,
+}
+
+fn solve(input: &str, crt: &Crt) -> Vec<String> {
     // Parse the instructions
     let ops = input
         .lines()
@@ -48,25 +71,27 @@ fn main() {
     let mut register: isize = 1;
     for op in ops {
         for _ in 0..op.duration() {
-            let x = (cycle % 40) as isize;
+            let x = cycle % crt.width as isize;
             cycle += 1;
             pixels.push((register - 1) <= x && (register + 1) >= x);
             if (cycle + 20) % 40 == 0 {
-                signal_strength += cycle * register;
+                signal_strength = checked::add(signal_strength, checked::mul(cycle, register));
             }
         }
         register = op.execute(register);
     }
-    println!("Solution 1: {signal_strength}");
 
-    for y in 0..6 {
-        for x in 0..40 {
-            if pixels[y * 40 + x] {
-                print!("#")
+    let mut screen = String::new();
+    for y in 0..crt.height {
+        for x in 0..crt.width {
+            if pixels.get(y * crt.width + x) == Some(&true) {
+                screen.push('#')
             } else {
-                print!(".")
+                screen.push('.')
             }
         }
-        println!();
+        screen.push('\n');
     }
+
+    vec![signal_strength.to_string(), screen]
 }
@@ -1,3 +1,4 @@
+use aoc_2022::{batch, checked, config::Config};
 use nom::{
     branch::alt,
     bytes::complete::tag,
@@ -6,18 +7,46 @@ use nom::{
     multi::{many0, separated_list0},
     sequence::{delimited, preceded, separated_pair, tuple},
 };
-use std::path::Path;
 
 fn main() {
+    let day = aoc_2022::day_name(file!());
+    let config = Config::from_env();
+    let rules = Rules {
+        rounds_part1: config.day_param(day, "rounds_part1", 20),
+        rounds_part2: config.day_param(day, "rounds_part2", 10000),
+        relief_factor: config.day_param(day, "relief_factor", 3),
+    };
+    if config.args().flag("checked") {
+        checked::enable(day);
+    }
+    if let Some(pattern) = config.args().value("batch") {
+        batch::run(&config, day, pattern, |input| solve(input, &rules));
+        return;
+    }
+
     // Read input file
-    let day = Path::new(file!()).file_stem().unwrap().to_str().unwrap();
-    let input = std::fs::read_to_string(format!("inputs/{day}.txt")).unwrap();
-    let input = complete(many0(parse_monkey))(&input).unwrap().1;
+    let input = config.read_input(day);
+    let answers = solve(&input, &rules);
+
+    println!("Solution day 11 part 1: {}", answers[0]);
+    println!("Solution day 11 part 2: {}", answers[1]);
+}
+
+/// How the monkeys play keep away. The worry level is divided by the relief factor after every
+/// inspection in part 1.
+struct Rules {
+    rounds_part1: usize,
+    rounds_part2: usize,
+    relief_factor: usize,
+}
+
+fn solve(input: &str, rules: &Rules) -> Vec<String> {
+    let input = complete(many0(parse_monkey))(input).unwrap().1;
 
     let mut monkeys = input.clone();
-    for _round in 0..20 {
+    for _round in 0..rules.rounds_part1 {
         for i in 0..monkeys.len() {
-            let packages = monkeys[i].do_round(3, 0);
+            let packages = monkeys[i].do_round(rules.relief_factor, 0);
             for package in packages {
                 monkeys[package.monkey_id].items.push(package.worry_level);
             }
@@ -27,13 +56,11 @@ fn main() {
     let mut monkey_business: Vec<_> = monkeys.iter().map(|m| m.items_inspected).collect();
     monkey_business.sort();
     monkey_business.reverse();
-    let monkey_business = monkey_business[0] * monkey_business[1];
-
-    println!("Solution day 11 part 1: {}", monkey_business);
+    let part1 = checked::mul(monkey_business[0], monkey_business[1]);
 
     let mut monkeys = input;
     let common_denominator = monkeys.iter().map(|m| m.test_divisible_by).product();
-    for _round in 0..10000 {
+    for _round in 0..rules.rounds_part2 {
         for i in 0..monkeys.len() {
             let packages = monkeys[i].do_round(1, common_denominator);
             for package in packages {
@@ -45,9 +72,9 @@ fn main() {
     let mut monkey_business: Vec<_> = monkeys.iter().map(|m| m.items_inspected).collect();
     monkey_business.sort();
     monkey_business.reverse();
-    let monkey_business = monkey_business[0] * monkey_business[1];
+    let part2 = checked::mul(monkey_business[0], monkey_business[1]);
 
-    println!("Solution day 11 part 2: {}", monkey_business);
+    vec![part1.to_string(), part2.to_string()]
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -101,8 +128,8 @@ impl Monkey {
 impl Operation {
     pub fn execute(&self, old: usize) -> usize {
         match self {
-            Operation::Multiple(left, right) => left.eval(old) * right.eval(old),
-            Operation::Add(left, right) => left.eval(old) + right.eval(old),
+            Operation::Multiple(left, right) => checked::mul(left.eval(old), right.eval(old)),
+            Operation::Add(left, right) => checked::add(left.eval(old), right.eval(old)),
         }
     }
 }
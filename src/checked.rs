@@ -0,0 +1,184 @@
+use std::fmt::{Display, Formatter};
+use std::ops::{Add, Mul, Sub};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static DAY: OnceLock<String> = OnceLock::new();
+
+/// Turns on checked arithmetic for the rest of the process. Overflows in the solvers of `day` are
+/// reported instead of wrapping around (release) or panicking without context (debug).
+pub fn enable(day: &str) {
+    DAY.get_or_init(|| day.to_owned());
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// An arithmetic operation in a solver that does not fit in its type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Overflow {
+    pub day: String,
+    pub operation: &'static str,
+    pub lhs: String,
+    pub rhs: String,
+}
+
+impl Display for Overflow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: arithmetic overflow in {} {} {}",
+            self.day, self.lhs, self.operation, self.rhs
+        )
+    }
+}
+
+/// Integer types the checked operations are available for.
+pub trait Integer:
+    Copy + Display + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self>
+{
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+}
+
+macro_rules! impl_integer {
+    ($($ty:ty),*) => {
+        $(impl Integer for $ty {
+            fn checked_add(self, rhs: Self) -> Option<Self> {
+                <$ty>::checked_add(self, rhs)
+            }
+            fn checked_sub(self, rhs: Self) -> Option<Self> {
+                <$ty>::checked_sub(self, rhs)
+            }
+            fn checked_mul(self, rhs: Self) -> Option<Self> {
+                <$ty>::checked_mul(self, rhs)
+            }
+        })*
+    };
+}
+
+impl_integer!(i32, i64, isize, u32, u64, usize);
+
+/// How the operations of a solver are done.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode<'a> {
+    /// As the type does it, wrapping around (release) or panicking (debug).
+    Unchecked,
+    /// Checked, overflows are reported as overflows in the solvers of `day`.
+    Checked { day: &'a str },
+}
+
+impl Mode<'_> {
+    /// The mode set for the process, see [`enable`].
+    pub fn current() -> Mode<'static> {
+        match is_enabled() {
+            true => Mode::Checked {
+                day: DAY.get().map_or("", String::as_str),
+            },
+            false => Mode::Unchecked,
+        }
+    }
+
+    pub fn add<T: Integer>(self, lhs: T, rhs: T) -> T {
+        match self {
+            Mode::Unchecked => lhs + rhs,
+            Mode::Checked { day } => lhs
+                .checked_add(rhs)
+                .unwrap_or_else(|| overflow(day, "+", lhs, rhs)),
+        }
+    }
+
+    pub fn sub<T: Integer>(self, lhs: T, rhs: T) -> T {
+        match self {
+            Mode::Unchecked => lhs - rhs,
+            Mode::Checked { day } => lhs
+                .checked_sub(rhs)
+                .unwrap_or_else(|| overflow(day, "-", lhs, rhs)),
+        }
+    }
+
+    pub fn mul<T: Integer>(self, lhs: T, rhs: T) -> T {
+        match self {
+            Mode::Unchecked => lhs * rhs,
+            Mode::Checked { day } => lhs
+                .checked_mul(rhs)
+                .unwrap_or_else(|| overflow(day, "*", lhs, rhs)),
+        }
+    }
+
+    pub fn sum<T: Integer>(self, zero: T, values: impl IntoIterator<Item = T>) -> T {
+        values.into_iter().fold(zero, |lhs, rhs| self.add(lhs, rhs))
+    }
+}
+
+/// Returns `lhs + rhs`, checked when checked arithmetic is enabled.
+pub fn add<T: Integer>(lhs: T, rhs: T) -> T {
+    Mode::current().add(lhs, rhs)
+}
+
+/// Returns `lhs - rhs`, checked when checked arithmetic is enabled.
+pub fn sub<T: Integer>(lhs: T, rhs: T) -> T {
+    Mode::current().sub(lhs, rhs)
+}
+
+/// Returns `lhs * rhs`, checked when checked arithmetic is enabled.
+pub fn mul<T: Integer>(lhs: T, rhs: T) -> T {
+    Mode::current().mul(lhs, rhs)
+}
+
+/// Sums the values with [`add`].
+pub fn sum<T: Integer>(zero: T, values: impl IntoIterator<Item = T>) -> T {
+    Mode::current().sum(zero, values)
+}
+
+fn overflow<T: Integer>(day: &str, operation: &'static str, lhs: T, rhs: T) -> ! {
+    let overflow = Overflow {
+        day: day.to_owned(),
+        operation,
+        lhs: lhs.to_string(),
+        rhs: rhs.to_string(),
+    };
+    panic!("{overflow}")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Mode, Overflow};
+    use std::panic::catch_unwind;
+
+    #[test]
+    fn test_checked_operations() {
+        let mode = Mode::Checked { day: "day11" };
+        assert_eq!(mode.add(2usize, 3), 5);
+        assert_eq!(mode.sub(5i32, 7), -2);
+        assert_eq!(mode.mul(4u64, 5), 20);
+        assert_eq!(mode.sum(0i32, [1, 2, 3]), 6);
+        assert_eq!(Mode::Unchecked.sum(0u32, [1, 2, 3]), 6);
+
+        let message = catch_unwind(|| mode.mul(usize::MAX, 2))
+            .unwrap_err()
+            .downcast::<String>()
+            .unwrap();
+        assert_eq!(
+            *message,
+            format!("day11: arithmetic overflow in {} * 2", usize::MAX)
+        );
+        assert!(catch_unwind(|| mode.sub(3usize, 4)).is_err());
+        assert!(catch_unwind(|| mode.sum(0i32, [i32::MAX, 1])).is_err());
+    }
+
+    #[test]
+    fn test_overflow_display() {
+        let overflow = Overflow {
+            day: "day07".to_owned(),
+            operation: "+",
+            lhs: "1".to_owned(),
+            rhs: "2".to_owned(),
+        };
+        assert_eq!(overflow.to_string(), "day07: arithmetic overflow in 1 + 2");
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Command line arguments of a day binary.
+///
+/// Options are written as `--name value` or `--name=value`, an option that is not followed by a
+/// value is a flag. Dashes and underscores in names are interchangeable.
+#[derive(Debug, Default, Clone)]
+pub struct Args {
+    options: HashMap<String, Option<String>>,
+}
+
+impl Args {
+    pub fn from_env() -> Self {
+        Self::parse(std::env::args().skip(1))
+    }
+
+    pub fn parse(args: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let mut options = HashMap::new();
+        let mut iter = args.into_iter().map(Into::into).peekable();
+        while let Some(arg) = iter.next() {
+            let Some(name) = arg.strip_prefix("--") else {
+                panic!("unexpected argument {arg:?}");
+            };
+            if let Some((name, value)) = name.split_once('=') {
+                options.insert(normalize(name), Some(value.to_owned()));
+            } else {
+                let value = iter.next_if(|next| !next.starts_with("--"));
+                options.insert(normalize(name), value);
+            }
+        }
+        Self { options }
+    }
+
+    /// Returns the value of an option, `None` if it was not given.
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.options.get(&normalize(name))?.as_deref()
+    }
+
+    /// Returns the value of an option parsed as `T`, panics if it cannot be parsed.
+    pub fn parsed<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.value(name).map(|value| match value.parse() {
+            Ok(value) => value,
+            Err(_) => panic!("invalid value {value:?} for --{name}"),
+        })
+    }
+
+    /// Returns true if the option was given, with or without a value.
+    pub fn flag(&self, name: &str) -> bool {
+        self.options.contains_key(&normalize(name))
+    }
+}
+
+fn normalize(name: &str) -> String {
+    name.replace('-', "_")
+}
+
+#[cfg(test)]
+mod test {
+    use super::Args;
+
+    #[test]
+    fn test_parse() {
+        let args = Args::parse([
+            "--batch",
+            "inputs/*.txt",
+            "--top=3",
+            "--json",
+            "--log-level",
+            "2",
+        ]);
+        assert_eq!(args.value("batch"), Some("inputs/*.txt"));
+        assert_eq!(args.parsed::<usize>("top"), Some(3));
+        assert!(args.flag("json"));
+        assert_eq!(args.value("json"), None);
+        assert_eq!(args.parsed::<usize>("log_level"), Some(2));
+        assert!(!args.flag("missing"));
+    }
+}
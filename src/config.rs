@@ -0,0 +1,209 @@
+use crate::cli::Args;
+use std::path::PathBuf;
+use std::str::FromStr;
+use toml::{Table, Value};
+
+/// The configuration file that is read from the working directory.
+pub const CONFIG_FILE: &str = "aoc.toml";
+
+/// How results are written to the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(OutputFormat::Table),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(format!("unknown output format {s:?}")),
+        }
+    }
+}
+
+/// Project settings from `aoc.toml`, overridable from the command line.
+///
+/// Every setting can be overridden with an option named after its section and key, e.g.
+/// `--bench.iterations 5` or `--day07.total-size-available 100`. Settings at the top of the file
+/// and the parameters of the day being run can also be overridden by key alone, e.g.
+/// `--input-dir other` or, in the day07 binary, `--total-size-available 100`.
+#[derive(Debug, Default)]
+pub struct Config {
+    document: Table,
+    args: Args,
+}
+
+impl Config {
+    /// Loads the configuration from `--config <path>` or `aoc.toml` when it exists.
+    pub fn load(args: Args) -> Self {
+        let path = args.value("config").unwrap_or(CONFIG_FILE);
+        let document = match std::fs::read_to_string(path) {
+            Ok(text) => match text.parse() {
+                Ok(document) => document,
+                Err(e) => panic!("invalid {path}: {e}"),
+            },
+            Err(_) if args.value("config").is_none() => Table::new(),
+            Err(e) => panic!("could not read {path}: {e}"),
+        };
+        Self { document, args }
+    }
+
+    pub fn from_env() -> Self {
+        Self::load(Args::from_env())
+    }
+
+    pub fn parse(text: &str, args: Args) -> Result<Self, toml::de::Error> {
+        Ok(Self {
+            document: text.parse()?,
+            args,
+        })
+    }
+
+    pub fn args(&self) -> &Args {
+        &self.args
+    }
+
+    /// Directory the puzzle inputs are read from.
+    pub fn input_dir(&self) -> PathBuf {
+        PathBuf::from(
+            self.get::<String>("", "input_dir", false)
+                .unwrap_or("inputs".to_owned()),
+        )
+    }
+
+    /// Path of the puzzle input of a day, `--input <path>` reads another file.
+    pub fn input_path(&self, day: &str) -> PathBuf {
+        match self.args.value("input") {
+            Some(path) => PathBuf::from(path),
+            None => self.input_dir().join(format!("{day}.txt")),
+        }
+    }
+
+    /// Reads the puzzle input of a day from the input directory.
+    pub fn read_input(&self, day: &str) -> String {
+        let path = self.input_path(day);
+        match std::fs::read_to_string(&path) {
+            Ok(input) => input,
+            Err(e) => panic!("could not read {}: {e}", path.display()),
+        }
+    }
+
+    pub fn output_format(&self) -> OutputFormat {
+        self.get("", "output_format", false).unwrap_or_default()
+    }
+
+    /// File in which known answers are stored, see [`crate::answers`].
+    pub fn answer_store(&self) -> Option<PathBuf> {
+        self.get::<String>("", "answer_store", false)
+            .map(PathBuf::from)
+    }
+
+    /// Number of times a solver is run when timing it.
+    pub fn iterations(&self) -> usize {
+        self.get("bench", "iterations", false).unwrap_or(1).max(1)
+    }
+
+    /// Returns a parameter from the section of `day`, or `default` if it is not configured.
+    pub fn day_param<T: FromStr>(&self, day: &str, key: &str, default: T) -> T {
+        self.get(day, key, true).unwrap_or(default)
+    }
+
+    /// Returns a setting of a section, `""` for the top of the file. The command line overrides it
+    /// with `--section.key`, or with `--key` for the top of the file or when `by_key` is set.
+    fn get<T: FromStr>(&self, section: &str, key: &str, by_key: bool) -> Option<T> {
+        let option = match section {
+            "" => key.to_owned(),
+            _ => format!("{section}.{key}"),
+        };
+        if let Some(value) = self.args.parsed(&option) {
+            return Some(value);
+        }
+        if by_key {
+            if let Some(value) = self.args.parsed(key) {
+                return Some(value);
+            }
+        }
+        let table = match section {
+            "" => &self.document,
+            _ => match self.document.get(section)? {
+                Value::Table(table) => table,
+                _ => panic!("{CONFIG_FILE}: `{section}` must be a section"),
+            },
+        };
+        let text = match table.get(key)? {
+            Value::String(s) => s.clone(),
+            value @ (Value::Integer(_) | Value::Float(_) | Value::Boolean(_)) => value.to_string(),
+            _ => panic!("{CONFIG_FILE}: `{key}` must be a string, a number or a boolean"),
+        };
+        match text.parse() {
+            Ok(value) => Some(value),
+            Err(_) => panic!("{CONFIG_FILE}: invalid value {text:?} for `{key}`"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Config, OutputFormat};
+    use crate::cli::Args;
+    use std::path::PathBuf;
+
+    const CONFIG: &str = "input_dir = \"my_inputs\"\n\
+                          output_format = \"json\"\n\
+                          answer_store = \"answers.toml\"\n\
+                          [bench]\n\
+                          iterations = 5\n\
+                          [day07]\n\
+                          total_size_available = 100\n\
+                          [day10]\n\
+                          total_size_available = 200\n";
+
+    #[test]
+    fn test_config_values() {
+        let config = Config::parse(CONFIG, Args::default()).unwrap();
+        assert_eq!(config.input_dir(), PathBuf::from("my_inputs"));
+        assert_eq!(config.output_format(), OutputFormat::Json);
+        assert_eq!(config.answer_store(), Some(PathBuf::from("answers.toml")));
+        assert_eq!(config.iterations(), 5);
+        assert_eq!(
+            config.day_param("day07", "total_size_available", 0usize),
+            100
+        );
+        assert_eq!(config.day_param("day07", "needed_free_size", 7usize), 7);
+        assert_eq!(config.day_param("day11", "total_size_available", 7usize), 7);
+    }
+
+    #[test]
+    fn test_cli_overrides() {
+        let args = Args::parse([
+            "--input-dir",
+            "other",
+            "--day10.total-size-available=5",
+            "--bench.iterations",
+            "2",
+            "--answer-store",
+            "mine.toml",
+        ]);
+        let config = Config::parse(CONFIG, args).unwrap();
+        assert_eq!(config.input_dir(), PathBuf::from("other"));
+        assert_eq!(config.iterations(), 2);
+        assert_eq!(config.answer_store(), Some(PathBuf::from("mine.toml")));
+        // A qualified option only overrides the setting of its own section.
+        assert_eq!(
+            config.day_param("day07", "total_size_available", 0usize),
+            100
+        );
+        assert_eq!(config.day_param("day10", "total_size_available", 0usize), 5);
+
+        // A key alone overrides the parameters of the day, not the other sections.
+        let args = Args::parse(["--iterations", "3", "--total-size-available", "7"]);
+        let config = Config::parse(CONFIG, args).unwrap();
+        assert_eq!(config.iterations(), 5);
+        assert_eq!(config.day_param("day07", "total_size_available", 0usize), 7);
+    }
+}
@@ -0,0 +1,315 @@
+//! Sets of integers stored as sorted, disjoint and non-adjacent inclusive ranges.
+
+use std::fmt::{Debug, Display, Formatter};
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+/// Integer types an [`IntervalSet`] can hold.
+pub trait Endpoint: Copy + Ord + Debug + Display + FromStr {
+    /// Returns `self + 1`, or `None` for the largest value.
+    fn next(self) -> Option<Self>;
+    /// Returns `self - 1`, or `None` for the smallest value.
+    fn previous(self) -> Option<Self>;
+    /// Returns the number of values in `start..=end`, saturating at `u64::MAX`.
+    fn count(start: Self, end: Self) -> u64;
+}
+
+macro_rules! impl_endpoint {
+    ($($ty:ty),*) => {
+        $(impl Endpoint for $ty {
+            fn next(self) -> Option<Self> {
+                self.checked_add(1)
+            }
+            fn previous(self) -> Option<Self> {
+                self.checked_sub(1)
+            }
+            fn count(start: Self, end: Self) -> u64 {
+                let span = (end as i128 - start as i128 + 1).max(0);
+                u64::try_from(span).unwrap_or(u64::MAX)
+            }
+        })*
+    };
+}
+
+impl_endpoint!(i32, i64, isize, u32, u64, usize);
+
+/// A set of integers, e.g. the sections assigned to a number of elves.
+#[derive(Clone, PartialEq, Eq)]
+pub struct IntervalSet<T> {
+    ranges: Vec<RangeInclusive<T>>,
+}
+
+impl<T: Endpoint> Default for IntervalSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Endpoint> IntervalSet<T> {
+    pub fn new() -> Self {
+        Self { ranges: Vec::new() }
+    }
+
+    /// The sorted ranges of the set, no two of them overlap or touch.
+    pub fn ranges(&self) -> &[RangeInclusive<T>] {
+        &self.ranges
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// The number of integers in the set.
+    pub fn len(&self) -> u64 {
+        self.ranges
+            .iter()
+            .map(|range| T::count(*range.start(), *range.end()))
+            .fold(0, u64::saturating_add)
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        let idx = self.ranges.partition_point(|range| *range.end() < value);
+        self.ranges
+            .get(idx)
+            .is_some_and(|range| *range.start() <= value)
+    }
+
+    pub fn insert(&mut self, range: RangeInclusive<T>) {
+        *self = self.union(&Self::from(range));
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        self.ranges.iter().chain(&other.ranges).cloned().collect()
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let (mut a, mut b) = (
+            self.ranges.iter().peekable(),
+            other.ranges.iter().peekable(),
+        );
+        while let (Some(x), Some(y)) = (a.peek(), b.peek()) {
+            let start = *x.start().max(y.start());
+            let end = *x.end().min(y.end());
+            if start <= end {
+                ranges.push(start..=end);
+            }
+            // The range that ends first cannot overlap anything else of the other set.
+            if x.end() < y.end() {
+                a.next();
+            } else {
+                b.next();
+            }
+        }
+        Self { ranges }
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let mut others = other.ranges.iter().peekable();
+        for range in &self.ranges {
+            let mut start = Some(*range.start());
+            // Skip the ranges that end before this one.
+            while others.next_if(|o| o.end() < range.start()).is_some() {}
+            let mut rest = others.clone();
+            while let (Some(from), Some(o)) = (start, rest.peek()) {
+                if o.start() > range.end() {
+                    break;
+                }
+                if from < *o.start() {
+                    ranges.push(from..=o.start().previous().unwrap());
+                }
+                start = o.end().next().filter(|next| next <= range.end());
+                if o.end() > range.end() {
+                    break;
+                }
+                rest.next();
+            }
+            if let Some(from) = start {
+                ranges.push(from..=*range.end());
+            }
+        }
+        Self { ranges }
+    }
+
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.ranges.iter().all(|range| other.contains_range(range))
+    }
+
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.ranges.iter().any(|range| other.overlaps_range(range))
+    }
+
+    /// Whether every value of `range` is in the set, [`Self::is_subset`] for a single range.
+    pub fn contains_range(&self, range: &RangeInclusive<T>) -> bool {
+        if range.is_empty() {
+            return true;
+        }
+        // The ranges neither overlap nor touch, so `range` must lie within a single one.
+        let idx = self.ranges.partition_point(|r| r.end() < range.start());
+        self.ranges
+            .get(idx)
+            .is_some_and(|r| r.start() <= range.start() && range.end() <= r.end())
+    }
+
+    /// Whether any value of `range` is in the set, [`Self::overlaps`] for a single range.
+    pub fn overlaps_range(&self, range: &RangeInclusive<T>) -> bool {
+        let idx = self.ranges.partition_point(|r| r.end() < range.start());
+        !range.is_empty()
+            && self
+                .ranges
+                .get(idx)
+                .is_some_and(|r| r.start() <= range.end())
+    }
+}
+
+impl<T: Endpoint> From<RangeInclusive<T>> for IntervalSet<T> {
+    fn from(range: RangeInclusive<T>) -> Self {
+        std::iter::once(range).collect()
+    }
+}
+
+/// Collects any ranges into a set, merging the ones that overlap or touch.
+impl<T: Endpoint> FromIterator<RangeInclusive<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = RangeInclusive<T>>>(iter: I) -> Self {
+        let mut sorted: Vec<_> = iter.into_iter().filter(|r| !r.is_empty()).collect();
+        sorted.sort_by_key(|range| *range.start());
+        let mut ranges: Vec<RangeInclusive<T>> = Vec::with_capacity(sorted.len());
+        for range in sorted {
+            match ranges.last_mut() {
+                Some(last) if last.end().next().is_none_or(|next| *range.start() <= next) => {
+                    *last = *last.start()..=*last.end().max(range.end());
+                }
+                _ => ranges.push(range),
+            }
+        }
+        Self { ranges }
+    }
+}
+
+impl<T: Endpoint> Debug for IntervalSet<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "IntervalSet({self})")
+    }
+}
+
+/// Formats the set as `2-4,6-8`, a range of one value as just the value.
+impl<T: Endpoint> Display for IntervalSet<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (idx, range) in self.ranges.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(",")?;
+            }
+            if range.start() == range.end() {
+                write!(f, "{}", range.start())?;
+            } else {
+                write!(f, "{}-{}", range.start(), range.end())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub message: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parses any number of comma separated ranges like `2-4,6,-3--1`.
+impl<T: Endpoint> FromStr for IntervalSet<T> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .filter(|part| !part.trim().is_empty())
+            .map(|part| parse_range(part.trim()))
+            .collect()
+    }
+}
+
+/// Parses `start-end` or a single value, either may be negative.
+pub fn parse_range<T: Endpoint>(s: &str) -> Result<RangeInclusive<T>, ParseError> {
+    let value = |part: &str| {
+        part.parse().map_err(|_| ParseError {
+            message: format!("invalid number `{part}` in `{s}`"),
+        })
+    };
+    // A `-` at the very start is a sign, not the separator.
+    match s.get(1..).and_then(|rest| rest.find('-')) {
+        Some(idx) => {
+            let (start, end) = (value(&s[..idx + 1])?, value(&s[idx + 2..])?);
+            if start > end {
+                return Err(ParseError {
+                    message: format!("range `{s}` ends before it starts"),
+                });
+            }
+            Ok(start..=end)
+        }
+        None => {
+            let value = value(s)?;
+            Ok(value..=value)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_range, IntervalSet};
+
+    fn set(s: &str) -> IntervalSet<i64> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(set("6-8,2-4,5").ranges(), [2..=8]);
+        assert_eq!(set("1-3,7-9,2-5").ranges(), [1..=5, 7..=9]);
+        assert_eq!(set("-5--3,-2").to_string(), "-5--2");
+        assert_eq!(set("").ranges(), []);
+        let max = IntervalSet::from_iter([0..=u64::MAX, 5..=6]);
+        assert_eq!(max.ranges(), [0..=u64::MAX]);
+        assert_eq!(max.len(), u64::MAX);
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a = set("1-10,20-30");
+        let b = set("5-25");
+        assert_eq!(a.union(&b), set("1-30"));
+        assert_eq!(a.intersection(&b), set("5-10,20-25"));
+        assert_eq!(a.difference(&b), set("1-4,26-30"));
+        assert_eq!(b.difference(&a), set("11-19"));
+        assert_eq!(set("1-10").difference(&set("2,4-5,9-12")), set("1,3,6-8"));
+        assert_eq!(set("1-3").difference(&set("0-5")), set(""));
+        assert_eq!(a.len(), 21);
+        assert!(set("3-7").is_subset(&set("2-8")));
+        assert!(!set("2-8").is_subset(&set("3-7")));
+        assert!(set("5-7").overlaps(&set("7-9")));
+        assert!(!set("2-4").overlaps(&set("6-8")));
+        assert!(set("1-3,5-7").is_subset(&set("1-8")) && !set("1-3,9").is_subset(&set("1-8")));
+        assert!(a.contains_range(&(22..=30)) && !a.contains_range(&(9..=21)));
+        assert!(a.overlaps_range(&(9..=21)) && !a.overlaps_range(&(11..=19)));
+        assert!(a.contains(20) && !a.contains(15) && !a.contains(31));
+
+        let mut c = IntervalSet::new();
+        c.insert(1..=2);
+        c.insert(3..=3);
+        assert_eq!(c, set("1-3"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_range::<i64>("4-2").is_err());
+        assert!(parse_range::<usize>("-1-2").is_err());
+        assert!("1-x".parse::<IntervalSet<i64>>().is_err());
+        assert_eq!(parse_range::<i64>("-3--1"), Ok(-3..=-1));
+    }
+}
@@ -1 +1,17 @@
+pub mod answers;
+pub mod batch;
+pub mod checked;
+pub mod cli;
+pub mod config;
+pub mod interval;
 
+use std::path::Path;
+
+/// Returns the name of the day a binary solves (e.g. `day06`), derived from its source file.
+pub fn day_name(source_file: &str) -> &str {
+    Path::new(source_file)
+        .file_stem()
+        .unwrap()
+        .to_str()
+        .unwrap()
+}